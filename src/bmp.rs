@@ -0,0 +1,101 @@
+use crate::PdfThumbError;
+#[cfg(feature = "image")]
+use crate::Rotation;
+
+/// See [Bitmap Storage](https://learn.microsoft.com/en-us/windows/win32/gdi/bitmap-storage).
+const FILE_HEADER_SIZE: usize = 14;
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+
+/// Decode a BMP file produced by the bitmap encoder into tightly packed RGBA bytes.
+pub fn to_rgba(bmp: &[u8]) -> Result<(Vec<u8>, u32, u32), PdfThumbError> {
+    let offset = read_u32(bmp, 10)? as usize;
+    let header_size = read_u32(bmp, FILE_HEADER_SIZE)? as usize;
+    let width = read_u32(bmp, 18)? as i32;
+    let height = read_u32(bmp, 22)? as i32;
+    let bpp = read_u16(bmp, 28)?;
+    let compression = read_u32(bmp, 30)?;
+
+    let masks = match (bpp, compression) {
+        (24 | 32, BI_RGB) => [0x00ff0000, 0x0000ff00, 0x000000ff, 0],
+        (32, BI_BITFIELDS) => {
+            // Masks follow BITMAPINFOHEADER, or are a part of BITMAPV4HEADER and later.
+            let alpha = if header_size >= 56 {
+                read_u32(bmp, FILE_HEADER_SIZE + 52)?
+            } else {
+                0
+            };
+            [
+                read_u32(bmp, FILE_HEADER_SIZE + 40)?,
+                read_u32(bmp, FILE_HEADER_SIZE + 44)?,
+                read_u32(bmp, FILE_HEADER_SIZE + 48)?,
+                alpha,
+            ]
+        }
+        _ => return Err(PdfThumbError::InvalidBitmap),
+    };
+
+    if width <= 0 || height == 0 {
+        return Err(PdfThumbError::InvalidBitmap);
+    }
+    let bottom_up = height > 0;
+    let width = width as usize;
+    let height = height.unsigned_abs() as usize;
+    let bytes_per_pixel = bpp as usize / 8;
+    let stride = (width * bpp as usize).div_ceil(32) * 4;
+
+    let pixels = bmp
+        .get(offset..offset + stride * height)
+        .ok_or(PdfThumbError::InvalidBitmap)?;
+
+    let mut buf = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        let row = if bottom_up { height - 1 - y } else { y };
+        let row = &pixels[row * stride..row * stride + width * bytes_per_pixel];
+        for px in row.chunks_exact(bytes_per_pixel) {
+            let mut value = [0; 4];
+            value[..bytes_per_pixel].copy_from_slice(px);
+            let value = u32::from_le_bytes(value);
+            let [r, g, b, a] = masks.map(|mask| channel(value, mask));
+            buf.extend_from_slice(&[r, g, b, if masks[3] == 0 { 0xff } else { a }]);
+        }
+    }
+    Ok((buf, width as u32, height as u32))
+}
+
+/// Decode a BMP file produced by the bitmap encoder into an RGBA image, applying the rotation.
+#[cfg(feature = "image")]
+pub fn to_image(bmp: &[u8], rotate: Rotation) -> Result<image::RgbaImage, PdfThumbError> {
+    use image::imageops;
+
+    let (pixels, width, height) = to_rgba(bmp)?;
+    let image =
+        image::RgbaImage::from_raw(width, height, pixels).ok_or(PdfThumbError::InvalidBitmap)?;
+    Ok(match rotate {
+        Rotation::None => image,
+        Rotation::Cw90 => imageops::rotate90(&image),
+        Rotation::Cw180 => imageops::rotate180(&image),
+        Rotation::Cw270 => imageops::rotate270(&image),
+    })
+}
+
+fn channel(value: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    ((value & mask) >> mask.trailing_zeros()) as u8
+}
+
+fn read_u16(buf: &[u8], offset: usize) -> Result<u16, PdfThumbError> {
+    buf.get(offset..offset + 2)
+        .and_then(|b| b.try_into().ok())
+        .map(u16::from_le_bytes)
+        .ok_or(PdfThumbError::InvalidBitmap)
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> Result<u32, PdfThumbError> {
+    buf.get(offset..offset + 4)
+        .and_then(|b| b.try_into().ok())
+        .map(u32::from_le_bytes)
+        .ok_or(PdfThumbError::InvalidBitmap)
+}
@@ -0,0 +1,164 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+#[cfg(target_os = "windows")]
+use std::{
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Mutex, OnceLock, Weak,
+    },
+    time::Instant,
+};
+#[cfg(target_os = "windows")]
+use windows::core::{Interface, RuntimeType};
+#[cfg(target_os = "windows")]
+use windows_future::{IAsyncAction, IAsyncInfo, IAsyncOperation};
+
+/// A token to cancel asynchronous rendering.
+///
+/// Cancellation is best-effort once rendering has started: the in-flight render is asked to stop, and the method returns [`PdfThumbError::Cancelled`](crate::PdfThumbError::Cancelled).
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    #[cfg(target_os = "windows")]
+    pending: Mutex<Vec<IAsyncInfo>>,
+}
+
+#[cfg(target_os = "windows")]
+unsafe impl Send for Inner {}
+#[cfg(target_os = "windows")]
+unsafe impl Sync for Inner {}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the rendering using this token.
+    pub fn cancel(&self) {
+        self.inner.cancel();
+    }
+
+    /// Whether the token is cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Await the action, cancelling it when the token is cancelled.
+    #[cfg(target_os = "windows")]
+    pub(crate) async fn run(&self, action: IAsyncAction) -> windows::core::Result<()> {
+        let _registration = self.register(&action)?;
+        action.await
+    }
+
+    /// Register the pending operation, so cancelling the token cancels it until the registration is dropped.
+    #[cfg(target_os = "windows")]
+    pub(crate) fn register(
+        &self,
+        operation: &impl Interface,
+    ) -> windows::core::Result<Registration<'_>> {
+        let info = operation.cast::<IAsyncInfo>()?;
+        let mut pending = self.inner.lock();
+        if self.is_cancelled() {
+            info.Cancel().ok();
+        } else {
+            pending.push(info.clone());
+        }
+        Ok(Registration { token: self, info })
+    }
+
+    /// Cancel the token at `deadline`. A single timer thread serves all tokens, and it does not keep a dropped token alive.
+    #[cfg(target_os = "windows")]
+    pub(crate) fn cancel_at(&self, deadline: Instant) {
+        static TIMER: OnceLock<Sender<(Instant, Weak<Inner>)>> = OnceLock::new();
+        let timer = TIMER.get_or_init(|| {
+            let (tx, rx) = mpsc::channel::<(Instant, Weak<Inner>)>();
+            std::thread::spawn(move || {
+                let mut timers: Vec<(Instant, Weak<Inner>)> = Vec::new();
+                loop {
+                    let next = timers.iter().map(|(deadline, _)| *deadline).min();
+                    let received = match next {
+                        Some(next) => {
+                            rx.recv_timeout(next.saturating_duration_since(Instant::now()))
+                        }
+                        None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                    };
+                    match received {
+                        Ok(timer) => timers.push(timer),
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                    let now = Instant::now();
+                    timers.retain(|(deadline, inner)| {
+                        if *deadline > now {
+                            return inner.strong_count() > 0;
+                        }
+                        if let Some(inner) = inner.upgrade() {
+                            inner.cancel();
+                        }
+                        false
+                    });
+                }
+            });
+            tx
+        });
+        timer.send((deadline, Arc::downgrade(&self.inner))).ok();
+    }
+}
+
+impl Inner {
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        #[cfg(target_os = "windows")]
+        for info in std::mem::take(&mut *self.lock()) {
+            info.Cancel().ok();
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<IAsyncInfo>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A pending operation registered with a [`CancellationToken`], unregistered when dropped.
+#[cfg(target_os = "windows")]
+pub(crate) struct Registration<'a> {
+    token: &'a CancellationToken,
+    info: IAsyncInfo,
+}
+
+#[cfg(target_os = "windows")]
+unsafe impl Send for Registration<'_> {}
+
+#[cfg(target_os = "windows")]
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        self.token.inner.lock().retain(|info| info != &self.info);
+    }
+}
+
+/// Register the operation with `token` if there is one. See [`CancellationToken::register`].
+#[cfg(target_os = "windows")]
+pub(crate) fn register<'a>(
+    token: Option<&'a CancellationToken>,
+    operation: &impl Interface,
+) -> windows::core::Result<Option<Registration<'a>>> {
+    token.map(|token| token.register(operation)).transpose()
+}
+
+/// Block on the operation, cancelling it when `token` is cancelled.
+#[cfg(target_os = "windows")]
+pub(crate) fn get<T: RuntimeType + 'static>(
+    operation: IAsyncOperation<T>,
+    token: Option<&CancellationToken>,
+) -> windows::core::Result<T> {
+    let _registration = register(token, &operation)?;
+    operation.get()
+}
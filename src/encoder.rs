@@ -0,0 +1,250 @@
+use crate::{
+    cancel::{self, get, CancellationToken},
+    win::{codec_error, read_bytes},
+    ImageFormat, Options, PdfThumbError, Rotation, TiffCompression,
+};
+#[cfg(feature = "image")]
+use windows::Storage::Streams::DataWriter;
+use windows::{
+    core::HSTRING,
+    Foundation::{PropertyType, PropertyValue},
+    Graphics::Imaging::{
+        BitmapAlphaMode, BitmapDecoder, BitmapEncoder, BitmapPixelFormat, BitmapPropertySet,
+        BitmapRotation, BitmapTypedValue,
+    },
+    Storage::Streams::InMemoryRandomAccessStream,
+};
+use windows_future::IAsyncOperation;
+
+/// Whether the rendered image has to be re-encoded to apply the options the renderer does not support.
+pub fn is_required(options: &Options) -> bool {
+    #[cfg(feature = "image")]
+    if options.grayscale {
+        return true;
+    }
+    #[cfg(feature = "tracing")]
+    if options.jpeg_quality.is_some() && options.image_format() != ImageFormat::Jpeg {
+        tracing::debug!(format = %options.image_format(), "jpeg_quality is ignored for non-JPEG formats");
+    }
+    (matches!(options.image_format(), ImageFormat::Jpeg) && options.jpeg_quality.is_some())
+        || (matches!(options.image_format(), ImageFormat::Tiff)
+            && options.tiff_compression.is_some())
+        || options.rotate != Rotation::None
+        || options.image_format().guid().is_none()
+}
+
+/// Re-encode the image of `input` into `output` with the specified options. The pending operations are cancelled when `token` is cancelled.
+pub fn encode(
+    input: &InMemoryRandomAccessStream,
+    output: &InMemoryRandomAccessStream,
+    options: &Options,
+    token: Option<&CancellationToken>,
+) -> Result<(), PdfThumbError> {
+    #[cfg(feature = "image")]
+    if options.grayscale {
+        return crate::gray::encode(input, output, options, token);
+    }
+    #[cfg(feature = "webp")]
+    if let ImageFormat::WebP = options.image_format() {
+        return crate::webp::encode(input, output, options);
+    }
+    input.Seek(0)?;
+    let decoder = get(BitmapDecoder::CreateAsync(input)?, token)?;
+    let bitmap = get(decoder.GetSoftwareBitmapAsync()?, token)?;
+    let encoder = get(create(output, options)?, token).map_err(codec_error)?;
+    encoder
+        .BitmapTransform()?
+        .SetRotation(options.rotate.into())?;
+    encoder.SetSoftwareBitmap(&bitmap).map_err(codec_error)?;
+    flush(&encoder, token)?;
+    Ok(())
+}
+
+/// Re-encode the image of `input` into `output` with the specified options asynchronously.
+pub async fn encode_async(
+    input: &InMemoryRandomAccessStream,
+    output: &InMemoryRandomAccessStream,
+    options: &Options,
+) -> Result<(), PdfThumbError> {
+    #[cfg(feature = "image")]
+    if options.grayscale {
+        return crate::gray::encode_async(input, output, options).await;
+    }
+    #[cfg(feature = "webp")]
+    if let ImageFormat::WebP = options.image_format() {
+        return crate::webp::encode_async(input, output, options).await;
+    }
+    input.Seek(0)?;
+    let decoder = BitmapDecoder::CreateAsync(input)?.await?;
+    let bitmap = decoder.GetSoftwareBitmapAsync()?.await?;
+    let encoder = create(output, options)?.await.map_err(codec_error)?;
+    encoder
+        .BitmapTransform()?
+        .SetRotation(options.rotate.into())?;
+    encoder.SetSoftwareBitmap(&bitmap).map_err(codec_error)?;
+    flush_async(&encoder).await?;
+    Ok(())
+}
+
+/// Encode tightly packed RGBA pixels into the specified format. The pending operations are cancelled when `token` is cancelled.
+pub fn encode_pixels(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    format: ImageFormat,
+    token: Option<&CancellationToken>,
+) -> Result<Vec<u8>, PdfThumbError> {
+    let Some(guid) = format.guid() else {
+        #[cfg(feature = "webp")]
+        return crate::webp::encode_pixels(pixels, width, height);
+        #[cfg(not(feature = "webp"))]
+        return Err(PdfThumbError::UnsupportedFormat);
+    };
+    let output = InMemoryRandomAccessStream::new()?;
+    let encoder = get(BitmapEncoder::CreateAsync(guid, &output)?, token).map_err(codec_error)?;
+    encoder
+        .SetPixelData(
+            BitmapPixelFormat::Rgba8,
+            BitmapAlphaMode::Straight,
+            width,
+            height,
+            DPI,
+            DPI,
+            pixels,
+        )
+        .map_err(codec_error)?;
+    flush(&encoder, token)?;
+    read_bytes(output)
+}
+
+/// Encode an 8-bit grayscale image into `output` with the specified options, cancelled with `token`. Only PNG, JPEG, TIFF and BMP encoders take the pixels natively.
+#[cfg(feature = "image")]
+pub fn encode_gray(
+    image: &image::GrayImage,
+    output: &InMemoryRandomAccessStream,
+    options: &Options,
+    token: Option<&CancellationToken>,
+) -> Result<(), PdfThumbError> {
+    let encoder = get(create(output, options)?, token).map_err(codec_error)?;
+    set_gray(&encoder, image).map_err(codec_error)?;
+    flush(&encoder, token)?;
+    Ok(())
+}
+
+/// Encode an 8-bit grayscale image into `output` with the specified options asynchronously.
+#[cfg(feature = "image")]
+pub async fn encode_gray_async(
+    image: &image::GrayImage,
+    output: &InMemoryRandomAccessStream,
+    options: &Options,
+) -> Result<(), PdfThumbError> {
+    let encoder = create(output, options)?.await.map_err(codec_error)?;
+    set_gray(&encoder, image).map_err(codec_error)?;
+    flush_async(&encoder).await?;
+    Ok(())
+}
+
+#[cfg(feature = "image")]
+fn set_gray(encoder: &BitmapEncoder, image: &image::GrayImage) -> windows::core::Result<()> {
+    encoder.SetPixelData(
+        BitmapPixelFormat::Gray8,
+        BitmapAlphaMode::Ignore,
+        image.width(),
+        image.height(),
+        DPI,
+        DPI,
+        image.as_raw(),
+    )
+}
+
+/// Flush the encoder. A missing codec, like HEVC for HEIF, can surface here rather than when the encoder is created.
+fn flush(encoder: &BitmapEncoder, token: Option<&CancellationToken>) -> Result<(), PdfThumbError> {
+    let action = encoder.FlushAsync()?;
+    let _registration = cancel::register(token, &action)?;
+    action.get().map_err(codec_error)
+}
+
+/// Flush the encoder asynchronously. See [`flush`].
+async fn flush_async(encoder: &BitmapEncoder) -> Result<(), PdfThumbError> {
+    encoder.FlushAsync()?.await.map_err(codec_error)
+}
+
+/// Write the encoded image into `output`.
+#[cfg(feature = "image")]
+pub fn write(output: &InMemoryRandomAccessStream, bytes: &[u8]) -> Result<(), PdfThumbError> {
+    let writer = DataWriter::CreateDataWriter(output)?;
+    writer.WriteBytes(bytes)?;
+    writer.StoreAsync()?.get()?;
+    writer.FlushAsync()?.get()?;
+    writer.DetachStream()?;
+    Ok(())
+}
+
+/// Write the encoded image into `output` asynchronously.
+#[cfg(feature = "image")]
+pub async fn write_async(
+    output: &InMemoryRandomAccessStream,
+    bytes: &[u8],
+) -> Result<(), PdfThumbError> {
+    let writer = DataWriter::CreateDataWriter(output)?;
+    writer.WriteBytes(bytes)?;
+    writer.StoreAsync()?.await?;
+    writer.FlushAsync()?.await?;
+    writer.DetachStream()?;
+    Ok(())
+}
+
+/// The resolution of the encoded image, matching the device independent pixels of the page.
+const DPI: f64 = 96.0;
+
+/// Read the pixel width and height of the image of `stream`.
+pub fn dimensions(stream: &InMemoryRandomAccessStream) -> Result<(u32, u32), PdfThumbError> {
+    stream.Seek(0)?;
+    let decoder = BitmapDecoder::CreateAsync(stream)?.get()?;
+    Ok((decoder.PixelWidth()?, decoder.PixelHeight()?))
+}
+
+fn create(
+    output: &InMemoryRandomAccessStream,
+    options: &Options,
+) -> Result<IAsyncOperation<BitmapEncoder>, PdfThumbError> {
+    let properties = BitmapPropertySet::new()?;
+    if let (ImageFormat::Jpeg, Some(quality)) = (options.image_format(), options.jpeg_quality) {
+        let value = PropertyValue::CreateSingle(quality.clamp(0.0, 1.0))?;
+        let value = BitmapTypedValue::Create(&value, PropertyType::Single)?;
+        properties.Insert(&HSTRING::from("ImageQuality"), &value)?;
+    }
+    if let (ImageFormat::Tiff, Some(compression)) =
+        (options.image_format(), options.tiff_compression)
+    {
+        let value = PropertyValue::CreateUInt8(compression_method(compression))?;
+        let value = BitmapTypedValue::Create(&value, PropertyType::UInt8)?;
+        properties.Insert(&HSTRING::from("TiffCompressionMethod"), &value)?;
+    }
+    let guid = options
+        .image_format()
+        .guid()
+        .ok_or(PdfThumbError::UnsupportedFormat)?;
+    BitmapEncoder::CreateWithEncodingOptionsAsync(guid, output, &properties).map_err(Into::into)
+}
+
+impl From<Rotation> for BitmapRotation {
+    fn from(rotation: Rotation) -> Self {
+        match rotation {
+            Rotation::None => Self::None,
+            Rotation::Cw90 => Self::Clockwise90Degrees,
+            Rotation::Cw180 => Self::Clockwise180Degrees,
+            Rotation::Cw270 => Self::Clockwise270Degrees,
+        }
+    }
+}
+
+/// See [TIFF Encoder Options](https://learn.microsoft.com/en-us/windows/win32/wic/-wic-creating-encoder#tiff-encoder-options).
+const fn compression_method(compression: TiffCompression) -> u8 {
+    match compression {
+        TiffCompression::None => 1,
+        TiffCompression::Ccitt4 => 3,
+        TiffCompression::Lzw => 4,
+        TiffCompression::Zip => 6,
+    }
+}
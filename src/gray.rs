@@ -0,0 +1,65 @@
+#[cfg(feature = "webp")]
+use crate::webp;
+use crate::{
+    bmp, cancel::CancellationToken, encoder, win::read_bytes, ImageFormat, Options, PdfThumbError,
+};
+use image::{imageops, DynamicImage, RgbaImage};
+use windows::Storage::Streams::InMemoryRandomAccessStream;
+
+/// Convert the lossless image of `input` into 8-bit grayscale and encode it into `output`, applying the rotation of the options. The pending operations are cancelled when `token` is cancelled.
+pub fn encode(
+    input: &InMemoryRandomAccessStream,
+    output: &InMemoryRandomAccessStream,
+    options: &Options,
+    token: Option<&CancellationToken>,
+) -> Result<(), PdfThumbError> {
+    let image = bmp::to_image(&read_bytes(input.clone())?, options.rotate)?;
+    match options.image_format() {
+        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Tiff | ImageFormat::Bmp => {
+            encoder::encode_gray(&imageops::grayscale(&image), output, options, token)
+        }
+        #[cfg(feature = "webp")]
+        ImageFormat::WebP => {
+            encoder::write(output, &webp::encode_gray(&imageops::grayscale(&image))?)
+        }
+        format => encoder::write(output, &encode_rgba(image, format, token)?),
+    }
+}
+
+/// Convert the lossless image of `input` into 8-bit grayscale and encode it into `output` asynchronously, applying the rotation of the options.
+pub async fn encode_async(
+    input: &InMemoryRandomAccessStream,
+    output: &InMemoryRandomAccessStream,
+    options: &Options,
+) -> Result<(), PdfThumbError> {
+    let image = bmp::to_image(&read_bytes(input.clone())?, options.rotate)?;
+    match options.image_format() {
+        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Tiff | ImageFormat::Bmp => {
+            encoder::encode_gray_async(&imageops::grayscale(&image), output, options).await
+        }
+        #[cfg(feature = "webp")]
+        ImageFormat::WebP => {
+            encoder::write_async(output, &webp::encode_gray(&imageops::grayscale(&image))?).await
+        }
+        format => encoder::write_async(output, &encode_rgba(image, format, None)?).await,
+    }
+}
+
+/// Convert tightly packed RGBA pixels into grayscale, keeping them RGBA with equal color channels.
+pub fn to_rgba(pixels: Vec<u8>, width: u32, height: u32) -> Result<Vec<u8>, PdfThumbError> {
+    let image = RgbaImage::from_raw(width, height, pixels).ok_or(PdfThumbError::InvalidBitmap)?;
+    Ok(DynamicImage::ImageLuma8(imageops::grayscale(&image))
+        .into_rgba8()
+        .into_raw())
+}
+
+/// Encode the image as RGBA with equal color channels, for the formats whose encoders do not take 8-bit grayscale pixels.
+fn encode_rgba(
+    image: RgbaImage,
+    format: ImageFormat,
+    token: Option<&CancellationToken>,
+) -> Result<Vec<u8>, PdfThumbError> {
+    let (width, height) = image.dimensions();
+    let pixels = to_rgba(image.into_raw(), width, height)?;
+    encoder::encode_pixels(&pixels, width, height, format, token)
+}
@@ -35,3 +35,10 @@ pub const GIF_ENCODER_ID: GUID = GUID {
     data3: 0x40a0,
     data4: [0x86, 0xa1, 0xc8, 0x3e, 0xa4, 0x95, 0xad, 0xbd],
 };
+
+pub const HEIF_ENCODER_ID: GUID = GUID {
+    data1: 0x0dbecec1,
+    data2: 0x9eb3,
+    data3: 0x4860,
+    data4: [0x9c, 0x6f, 0xdd, 0xbe, 0x86, 0x63, 0x45, 0x75],
+};
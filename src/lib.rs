@@ -2,7 +2,7 @@
 //!
 //! # Example
 //!
-//! ```rust
+//! ```rust,no_run
 //! use anyhow::Result;
 //! use pdf_thumb::PdfDoc;
 //!
@@ -16,16 +16,16 @@
 //!
 //! Some options and async operation are also available.
 //!
-//! ```rust
+//! ```rust,no_run
 //! use anyhow::Result;
 //! use pdf_thumb::{ImageFormat, Options, PdfDoc};
 //!
 //! #[tokio::main]
-//! fn main() -> Result<()> {
+//! async fn main() -> Result<()> {
 //!     let pdf = PdfDoc::open_async("test.pdf").await?;
 //!     let options = Options {
 //!         width: 320,                // Set thumbnail image width.
-//!         format: ImageFormat::Jpeg, // Set thumbnail image format.
+//!         format: Some(ImageFormat::Jpeg), // Set thumbnail image format.
 //!         ..Default::default()
 //!     };
 //!     let thumb = pdf.thumb_with_options_async(options).await?;
@@ -34,39 +34,116 @@
 //! }
 //! ```
 //!
+//! The rendering is only available on Windows. On other platforms the same API is exposed, but loading a document returns [`PdfThumbError::Unsupported`], so that cross-platform crates still compile.
+//!
 //! - [crates.io](https://crates.io/crates/pdf-thumb)
 //! - [Repository](https://github.com/zxrs/pdf-thumb)
 
-#![cfg(target_os = "windows")]
-
 use std::{
-    ops::{Deref, DivAssign},
-    path::Path,
+    fmt,
+    ops::{Div, DivAssign, Mul, MulAssign},
+    path::{Path, PathBuf},
+    str::FromStr,
 };
 use thiserror::Error;
-use windows::{
-    core::{GUID, HSTRING},
-    Data::Pdf::{PdfDocument as PdfDocument_, PdfPage as PdfPage_, PdfPageRenderOptions},
-    Foundation,
-    Storage::{
-        StorageFile,
-        Streams::{DataReader, DataWriter, InMemoryRandomAccessStream},
-    },
-};
-use windows_future::{IAsyncAction, IAsyncOperation};
-
+#[cfg(target_os = "windows")]
+use windows::core::GUID;
+
+#[cfg(target_os = "windows")]
+mod bmp;
+mod cancel;
+#[cfg(target_os = "windows")]
+mod encoder;
+#[cfg(all(target_os = "windows", feature = "image"))]
+mod gray;
+#[cfg(target_os = "windows")]
 mod guid;
-use guid::*;
-
+#[cfg(target_os = "windows")]
+mod parallel;
+#[cfg(not(target_os = "windows"))]
+mod stub;
+#[cfg(all(target_os = "windows", feature = "tracing"))]
+mod trace;
+#[cfg(all(target_os = "windows", feature = "webp"))]
+mod webp;
+#[cfg(target_os = "windows")]
+mod win;
+
+pub use cancel::CancellationToken;
+#[cfg(not(target_os = "windows"))]
+pub use stub::{PdfDocument, PdfPage, Renderer};
+#[cfg(target_os = "windows")]
+pub use win::{PdfDocument, PdfPage, Renderer};
+
+/// The error of this crate. Some variants only exist with their feature or platform, so it is non-exhaustive to keep features additive.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum PdfThumbError {
     #[error("io error")]
     Io(#[from] std::io::Error),
-    #[error("windows error")]
+    #[cfg(target_os = "windows")]
+    #[error("windows error: {0}")]
     Windows(#[from] windows::core::Error),
+    #[error("file not found: {}", .0.display())]
+    FileNotFound(PathBuf),
+    #[error("access denied: {}", .0.display())]
+    AccessDenied(PathBuf),
+    #[cfg(all(target_os = "windows", feature = "url"))]
+    #[error("network error: {0}")]
+    Network(windows::core::Error),
+    #[error("password required")]
+    PasswordRequired,
+    #[error("wrong password")]
+    WrongPassword,
+    #[error("page {requested} is out of range, the document has {count} pages")]
+    PageOutOfRange { requested: u32, count: u32 },
+    #[error("rect is out of the page bounds of {}x{}", .size.width(), .size.height())]
+    RectOutOfBounds { rect: Rect, size: Size },
+    #[error("cancelled")]
+    Cancelled,
+    #[error("timed out")]
+    Timeout,
+    #[error("failed to render page {index}")]
+    Page {
+        index: u32,
+        source: Box<PdfThumbError>,
+    },
+    #[error("invalid bitmap")]
+    InvalidBitmap,
+    #[cfg(feature = "rayon")]
+    #[error("thread pool error")]
+    ThreadPool(#[from] rayon::ThreadPoolBuildError),
+    #[cfg(feature = "image")]
+    #[error("image error: {0}")]
+    Decode(#[from] image::ImageError),
+    #[error("image format is not supported")]
+    UnsupportedFormat,
+    #[error("unknown image format: {0}")]
+    UnknownFormat(String),
+    #[error("codec is not installed")]
+    CodecNotInstalled,
+    #[error("invalid options: {0}")]
+    InvalidOptions(&'static str),
+    #[error("not supported on this platform")]
+    Unsupported,
+}
+
+impl PdfThumbError {
+    /// Get the `HRESULT` of the underlying Windows error, if any. For [`PdfThumbError::Page`], the `HRESULT` of its source is returned.
+    pub fn hresult(&self) -> Option<i32> {
+        match self {
+            #[cfg(target_os = "windows")]
+            Self::Windows(e) => Some(e.code().0),
+            #[cfg(all(target_os = "windows", feature = "url"))]
+            Self::Network(e) => Some(e.code().0),
+            Self::Page { source, .. } => source.hresult(),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rect {
     pub x: u32,
     pub y: u32,
@@ -74,215 +151,475 @@ pub struct Rect {
     pub height: u32,
 }
 
-impl From<Rect> for Foundation::Rect {
-    fn from(r: Rect) -> Self {
+impl Rect {
+    pub const fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
         Self {
-            X: r.x as _,
-            Y: r.y as _,
-            Width: r.width as _,
-            Height: r.height as _,
+            x,
+            y,
+            width,
+            height,
         }
     }
+
+    /// Create a rect of the whole page of the specified size, rounding the size to pixels.
+    pub fn from_size(size: Size) -> Self {
+        Self::new(
+            0,
+            0,
+            size.width().round() as u32,
+            size.height().round() as u32,
+        )
+    }
 }
 
-#[derive(Debug, Default, Clone, Copy)]
+impl From<(u32, u32, u32, u32)> for Rect {
+    fn from((x, y, width, height): (u32, u32, u32, u32)) -> Self {
+        Self::new(x, y, width, height)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Options {
     /// The destination width of the rendered page. If `width` is not specified, the page's aspect ratio is maintained relative to the destination height.
     pub width: u32,
     /// The destination height of the rendered page. If `height` is not specified, the page's aspect ratio is maintained relative to the destination width.
     pub height: u32,
-    /// The portion of the PDF page to be rendered. If `rect` is not specified, the whole page is rendered.
+    /// The portion of the PDF page to be rendered. If `rect` is not specified, the whole page is rendered. A rect with zero width or height is rejected with [`PdfThumbError::InvalidOptions`].
+    /// When both `width` and `height` are specified, the rect is scaled to them independently of its aspect ratio, unless `preserve_source_aspect` or `scale_mode` is set.
     pub rect: Rect,
     /// The page index to be rendered. If `page` is not specified, the first page is rendered.
     pub page: u32,
-    /// The image format of thumbnail. If `format` is not specified, PNG format is used.
-    pub format: ImageFormat,
+    /// The image format of thumbnail. If `format` is not specified, PNG format is used, except that [`PdfDocument::thumb_to_file`] infers it from the extension of the path first.
+    pub format: Option<ImageFormat>,
+    /// The background color of the rendered page. If `background` is not specified, the default white is used. Alpha is honored only for formats that support transparency like PNG.
+    pub background: Option<Color>,
+    /// Whether the page is rendered ignoring the high contrast mode of Windows. If `ignore_high_contrast` is not specified, the high contrast mode is honored. Set `true` for server-side rendering.
+    pub ignore_high_contrast: bool,
+    /// The quality of JPEG image from `0.0` to `1.0`. Out of range values are clamped. If `jpeg_quality` is not specified, the default quality of the encoder is used. It is ignored for other formats, which is logged at debug level with the `tracing` feature.
+    pub jpeg_quality: Option<f32>,
+    /// The compression method of TIFF image. If `tiff_compression` is not specified, the default method of the encoder is used. It is ignored for other formats.
+    pub tiff_compression: Option<TiffCompression>,
+    /// The resolution of the rendered page in dots per inch. The destination width and height are computed from the size of `rect`, or of the page if `rect` is not specified, so `dpi` can not be specified with `width` or `height`.
+    pub dpi: Option<f32>,
+    /// How the page is scaled when both `width` and `height` are specified. If `scale_mode` is not specified, the page is scaled to exactly `width` and `height`.
+    pub scale_mode: ScaleMode,
+    /// The rotation applied to the rendered image. If `rotate` is not specified, the image is not rotated. Rotating incurs a decode/encode round-trip of the rendered image.
+    pub rotate: Rotation,
+    /// Whether the destination height is derived from the aspect ratio of `rect`, or of the page if `rect` is not specified, and `width`. It is ignored if `width` is not specified.
+    pub preserve_source_aspect: bool,
+    /// Whether the rendered image is converted to 8-bit grayscale. PNG, JPEG, TIFF, BMP and WebP images are true grayscale rather than RGB with equal channels, and other formats are RGB with equal channels.
+    ///
+    /// Converting is not free: the page is rendered to an intermediate BMP, which is decoded into memory with the `image` crate, converted and encoded again. This adds a full decode/encode round trip and a copy of the pixels to every render.
+    #[cfg(feature = "image")]
+    pub grayscale: bool,
 }
 
-unsafe impl Send for Options {}
-unsafe impl Sync for Options {}
+/// How the page is scaled into the box of the destination `width` and `height`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ScaleMode {
+    /// Scale to exactly the destination size. The aspect ratio is not preserved.
+    #[default]
+    Exact,
+    /// Scale to fit within the destination size, preserving the aspect ratio.
+    Fit,
+    /// Scale to cover the destination size, preserving the aspect ratio. The overflow is cropped.
+    Fill,
+}
+
+/// The compression method of TIFF image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum TiffCompression {
+    None,
+    Lzw,
+    Zip,
+    /// CCITT Group 4 fax compression, which supports only bilevel images.
+    Ccitt4,
+}
+
+/// The clockwise rotation applied to the rendered image.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Rotation {
+    #[default]
+    None,
+    Cw90,
+    Cw180,
+    Cw270,
+}
+
+impl Options {
+    /// Create a builder of the options.
+    pub fn builder() -> OptionsBuilder {
+        OptionsBuilder::default()
+    }
+
+    /// The image format to encode the thumbnail into, which is PNG if `format` is not specified.
+    pub fn image_format(&self) -> ImageFormat {
+        self.format.unwrap_or_default()
+    }
 
-impl TryFrom<Options> for PdfPageRenderOptions {
-    type Error = PdfThumbError;
-    fn try_from(options: Options) -> Result<Self, Self::Error> {
-        let op = PdfPageRenderOptions::new()?;
-        if options.width > 0 {
-            op.SetDestinationWidth(options.width)?;
+    /// Compute the pixel size of the image rendered from a page of `page_size` with the options, without rendering it.
+    ///
+    /// Options which fail to render, like `dpi` specified with `width`, are not detected.
+    pub fn rendered_size(&self, page_size: Size) -> Size {
+        let size = self.scaled_size(self.source_size(page_size));
+        match self.rotate {
+            Rotation::Cw90 | Rotation::Cw270 => Size {
+                width: size.height,
+                height: size.width,
+            },
+            _ => size,
         }
-        if options.height > 0 {
-            op.SetDestinationHeight(options.height)?;
+    }
+
+    /// The size of the portion of a page of `page_size` to be rendered, which is `rect` if it is specified.
+    pub(crate) fn source_size(&self, page_size: Size) -> Size {
+        if self.rect.ne(&Rect::default()) {
+            return Size {
+                width: self.rect.width as f32,
+                height: self.rect.height as f32,
+            };
         }
-        if options.rect.ne(&Rect::default()) {
-            op.SetSourceRect(options.rect.into())?;
+        page_size
+    }
+
+    /// The pixel size of the image rendered from `source` before rotation, applying `dpi`, `preserve_source_aspect` and `scale_mode`.
+    pub(crate) fn scaled_size(&self, source: Size) -> Size {
+        let (mut width, mut height) = (self.width as f32, self.height as f32);
+        if let (Some(dpi), 0, 0) = (self.dpi, self.width, self.height) {
+            width = (source.width / DIPS_PER_INCH * dpi).round();
+            height = (source.height / DIPS_PER_INCH * dpi).round();
         }
-        op.SetBitmapEncoderId(options.format.guid())?;
-        Ok(op)
+        if self.preserve_source_aspect && width > 0.0 {
+            height = source.scale_to_width(width).round();
+        }
+        let (width, height) = match (width > 0.0, height > 0.0) {
+            (false, false) => (source.width.round(), source.height.round()),
+            (true, false) => (width, source.scale_to_width(width).round()),
+            (false, true) => (source.scale_to_height(height).round(), height),
+            (true, true) if self.scale_mode == ScaleMode::Fit => {
+                if source.aspect_ratio() > width / height {
+                    (width, source.scale_to_width(width).round())
+                } else {
+                    (source.scale_to_height(height).round(), height)
+                }
+            }
+            (true, true) => (width, height),
+        };
+        Size { width, height }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum ImageFormat {
-    Png,
-    Bmp,
-    Jpeg,
-    Tiff,
-    Gif,
+unsafe impl Send for Options {}
+unsafe impl Sync for Options {}
+
+/// A builder of [`Options`].
+///
+/// ```rust,no_run
+/// use pdf_thumb::{ImageFormat, Options};
+///
+/// let options = Options::builder()
+///     .width(320)
+///     .format(ImageFormat::Jpeg)
+///     .build();
+///
+/// // Same as above.
+/// let options = Options::builder().width(320).jpeg().build();
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OptionsBuilder {
+    options: Options,
 }
 
-impl Default for ImageFormat {
-    fn default() -> Self {
-        Self::Png
+impl OptionsBuilder {
+    /// Set the destination width of the rendered page.
+    pub fn width(mut self, width: u32) -> Self {
+        self.options.width = width;
+        self
     }
-}
 
-impl ImageFormat {
-    const fn guid(&self) -> GUID {
-        use ImageFormat::*;
-        match self {
-            Png => PNG_ENCORDER_ID,
-            Bmp => BITMAP_ENCODER_ID,
-            Jpeg => JPEG_ENCORDER_ID,
-            Tiff => TIFF_ENCODER_ID,
-            Gif => GIF_ENCODER_ID,
-        }
+    /// Set the destination height of the rendered page.
+    pub fn height(mut self, height: u32) -> Self {
+        self.options.height = height;
+        self
     }
-}
 
-#[derive(Debug)]
-pub struct PdfDocument {
-    doc: PdfDocument_,
-}
+    /// Set the portion of the PDF page to be rendered.
+    pub fn rect(mut self, rect: Rect) -> Self {
+        self.options.rect = rect;
+        self
+    }
 
-unsafe impl Send for PdfDocument {}
-unsafe impl Sync for PdfDocument {}
+    /// Set the page index to be rendered.
+    pub fn page(mut self, page: u32) -> Self {
+        self.options.page = page;
+        self
+    }
 
-impl PdfDocument {
-    /// Load a PDF document from memory.
-    pub fn load(pdf: &[u8]) -> Result<Self, PdfThumbError> {
-        let stream = InMemoryRandomAccessStream::new()?;
-        let writer = DataWriter::CreateDataWriter(&stream)?;
-        writer.WriteBytes(pdf)?;
-        writer.StoreAsync()?.get()?;
-        writer.FlushAsync()?.get()?;
-        writer.DetachStream()?;
-        let doc = PdfDocument_::LoadFromStreamAsync(&stream)?.get()?;
-        Ok(Self { doc })
+    /// Set the image format of thumbnail.
+    pub fn format(mut self, format: ImageFormat) -> Self {
+        self.options.format = Some(format);
+        self
     }
 
-    /// Open a PDF document from a path.
-    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, PdfThumbError> {
-        let file = get_file(path)?.get()?;
-        let doc = open(&file)?.get()?;
-        Ok(Self { doc })
+    /// Set the image format to PNG.
+    pub fn png(self) -> Self {
+        self.format(ImageFormat::Png)
     }
 
-    /// Open a PDF document from a path asynchronously.
-    pub async fn open_async<P: AsRef<Path>>(path: P) -> Result<Self, PdfThumbError> {
-        let file = get_file(path)?.await?;
-        let doc = open(&file)?.await?;
-        Ok(Self { doc })
+    /// Set the image format to BMP.
+    pub fn bmp(self) -> Self {
+        self.format(ImageFormat::Bmp)
     }
 
-    /// Get the number of PDF document.
-    pub fn page_count(&self) -> Result<u32, PdfThumbError> {
-        Ok(self.doc.PageCount()?)
+    /// Set the image format to JPEG.
+    pub fn jpeg(self) -> Self {
+        self.format(ImageFormat::Jpeg)
     }
 
-    /// Generate a thumbnail image with default options.
-    pub fn thumb(&self) -> Result<Vec<u8>, PdfThumbError> {
-        let options = Options::default();
-        self.thumb_with_options(options)
+    /// Set the image format to TIFF.
+    pub fn tiff(self) -> Self {
+        self.format(ImageFormat::Tiff)
     }
 
-    /// Generate a thumbnail image with default options asynchronously.
-    pub async fn thumb_async(&self) -> Result<Vec<u8>, PdfThumbError> {
-        let options = Options::default();
-        self.thumb_with_options_async(options).await
+    /// Set the image format to GIF.
+    pub fn gif(self) -> Self {
+        self.format(ImageFormat::Gif)
     }
 
-    /// Generate a thumbnail image with the specified options.
-    pub fn thumb_with_options(&self, options: Options) -> Result<Vec<u8>, PdfThumbError> {
-        let page = self.get_page(options.page)?;
-        let output = InMemoryRandomAccessStream::new()?;
-        render(page, &output, options)?.get()?;
-        read_bytes(output)
+    /// Set the compression method of TIFF image.
+    pub fn tiff_compression(mut self, tiff_compression: TiffCompression) -> Self {
+        self.options.tiff_compression = Some(tiff_compression);
+        self
     }
 
-    /// Generate a thumbnail image with the specified options asynchronously.
-    pub async fn thumb_with_options_async(
-        &self,
-        options: Options,
-    ) -> Result<Vec<u8>, PdfThumbError> {
-        let page = self.get_page(options.page)?;
-        let output = InMemoryRandomAccessStream::new()?;
-        render(page, &output, options)?.await?;
-        read_bytes(output)
+    /// Set the resolution of the rendered page in dots per inch.
+    pub fn dpi(mut self, dpi: f32) -> Self {
+        self.options.dpi = Some(dpi);
+        self
     }
 
-    pub fn get_page(&self, page_index: u32) -> Result<PdfPage, PdfThumbError> {
-        let page = self.doc.GetPage(page_index)?;
-        Ok(PdfPage::new(page))
+    /// Set how the page is scaled when both width and height are specified.
+    pub fn scale_mode(mut self, scale_mode: ScaleMode) -> Self {
+        self.options.scale_mode = scale_mode;
+        self
     }
-}
 
-fn get_file<P: AsRef<Path>>(path: P) -> Result<IAsyncOperation<StorageFile>, PdfThumbError> {
-    let path = HSTRING::from(path.as_ref());
-    StorageFile::GetFileFromPathAsync(&path).map_err(Into::into)
-}
+    /// Set the rotation applied to the rendered image.
+    pub fn rotate(mut self, rotate: Rotation) -> Self {
+        self.options.rotate = rotate;
+        self
+    }
+
+    /// Set whether the destination height is derived from the aspect ratio of the source and the width.
+    pub fn preserve_source_aspect(mut self, preserve_source_aspect: bool) -> Self {
+        self.options.preserve_source_aspect = preserve_source_aspect;
+        self
+    }
+
+    /// Set whether the rendered image is converted to 8-bit grayscale. See [`Options::grayscale`] for the extra decode/encode cost.
+    #[cfg(feature = "image")]
+    pub fn grayscale(mut self, grayscale: bool) -> Self {
+        self.options.grayscale = grayscale;
+        self
+    }
+
+    /// Set the background color of the rendered page.
+    pub fn background(mut self, background: Color) -> Self {
+        self.options.background = Some(background);
+        self
+    }
+
+    /// Set whether the page is rendered ignoring the high contrast mode of Windows.
+    pub fn ignore_high_contrast(mut self, ignore_high_contrast: bool) -> Self {
+        self.options.ignore_high_contrast = ignore_high_contrast;
+        self
+    }
+
+    /// Set the quality of JPEG image from `0.0` to `1.0`.
+    pub fn jpeg_quality(mut self, jpeg_quality: f32) -> Self {
+        self.options.jpeg_quality = Some(jpeg_quality);
+        self
+    }
 
-fn open(file: &StorageFile) -> Result<IAsyncOperation<PdfDocument_>, PdfThumbError> {
-    PdfDocument_::LoadFromFileAsync(file).map_err(Into::into)
+    /// Build the options.
+    pub fn build(self) -> Options {
+        self.options
+    }
 }
 
-fn render(
-    page: PdfPage,
-    output: &InMemoryRandomAccessStream,
-    options: Options,
-) -> Result<IAsyncAction, PdfThumbError> {
-    page.RenderWithOptionsToStreamAsync(output, options.try_into().as_ref().ok())
-        .map_err(Into::into)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[non_exhaustive]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    Bmp,
+    Jpeg,
+    Tiff,
+    Gif,
+    /// HEIF requires the HEVC codec extension to be installed.
+    Heif,
+    /// WebP is encoded by the `image` crate from a lossless rendering, which costs an extra decode and encode of the image.
+    #[cfg(feature = "webp")]
+    WebP,
+    /// A bitmap encoder registered on the machine, specified by its GUID. A GUID of no registered encoder makes rendering fail with [`PdfThumbError::UnsupportedFormat`].
+    #[cfg(target_os = "windows")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(GUID),
 }
 
-fn read_bytes(output: InMemoryRandomAccessStream) -> Result<Vec<u8>, PdfThumbError> {
-    let input = output.GetInputStreamAt(0)?;
-    let reader = DataReader::CreateDataReader(&input)?;
-    let size = output.Size()?;
-    reader.LoadAsync(size as u32)?.get()?;
-    let mut buf = vec![0; size as usize];
-    reader.ReadBytes(&mut buf)?;
-    Ok(buf)
+impl FromStr for ImageFormat {
+    type Err = PdfThumbError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_extension(s).ok_or_else(|| PdfThumbError::UnknownFormat(s.to_string()))
+    }
 }
 
-#[derive(Debug)]
-pub struct PdfPage {
-    page: PdfPage_,
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ImageFormat::*;
+        let name = match self {
+            Png => "png",
+            Bmp => "bmp",
+            Jpeg => "jpeg",
+            Tiff => "tiff",
+            Gif => "gif",
+            Heif => "heif",
+            #[cfg(feature = "webp")]
+            WebP => "webp",
+            #[cfg(target_os = "windows")]
+            Custom(guid) => return write!(f, "custom({guid:?})"),
+        };
+        f.write_str(name)
+    }
 }
 
-unsafe impl Sync for PdfPage {}
-unsafe impl Send for PdfPage {}
+impl ImageFormat {
+    /// Get the image format from a file extension like `"png"` or `"jpg"`, ignoring case.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        use ImageFormat::*;
+        match ext.to_ascii_lowercase().as_str() {
+            "png" => Some(Png),
+            "bmp" => Some(Bmp),
+            "jpg" | "jpeg" => Some(Jpeg),
+            "tif" | "tiff" => Some(Tiff),
+            "gif" => Some(Gif),
+            "heif" | "heic" => Some(Heif),
+            #[cfg(feature = "webp")]
+            "webp" => Some(WebP),
+            _ => None,
+        }
+    }
 
-impl Deref for PdfPage {
-    type Target = PdfPage_;
+    /// Get the image format from the extension of a path like `"thumb.jpg"`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?;
+        Self::from_extension(ext)
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.page
+    /// Get the image format from a MIME type like `"image/png"`, ignoring case.
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        use ImageFormat::*;
+        match mime.to_ascii_lowercase().as_str() {
+            "image/png" => Some(Png),
+            "image/bmp" => Some(Bmp),
+            "image/jpeg" => Some(Jpeg),
+            "image/tiff" => Some(Tiff),
+            "image/gif" => Some(Gif),
+            "image/heif" | "image/heic" => Some(Heif),
+            #[cfg(feature = "webp")]
+            "image/webp" => Some(WebP),
+            _ => None,
+        }
     }
-}
 
-impl PdfPage {
-    pub fn new(page: PdfPage_) -> Self {
-        Self { page }
+    /// Get the MIME type of the image format like `"image/png"`.
+    pub const fn mime_type(&self) -> &'static str {
+        use ImageFormat::*;
+        match self {
+            Png => "image/png",
+            Bmp => "image/bmp",
+            Jpeg => "image/jpeg",
+            Tiff => "image/tiff",
+            Gif => "image/gif",
+            Heif => "image/heif",
+            #[cfg(feature = "webp")]
+            WebP => "image/webp",
+            #[cfg(target_os = "windows")]
+            Custom(_) => "application/octet-stream",
+        }
     }
 
-    pub fn size(&self) -> Result<Size, PdfThumbError> {
-        Ok(self.page.Size()?.into())
+    /// Get the common file extension of the image format like `"png"`, without the leading dot. `"bin"` is returned for a custom format.
+    pub const fn extension(&self) -> &'static str {
+        use ImageFormat::*;
+        match self {
+            Png => "png",
+            Bmp => "bmp",
+            Jpeg => "jpg",
+            Tiff => "tiff",
+            Gif => "gif",
+            Heif => "heif",
+            #[cfg(feature = "webp")]
+            WebP => "webp",
+            #[cfg(target_os = "windows")]
+            Custom(_) => "bin",
+        }
     }
 }
 
-impl Drop for PdfPage {
-    fn drop(&mut self) {
-        self.page.Close().ok();
-    }
+/// Short alias of [`PdfDocument`].
+pub type PdfDoc = PdfDocument;
+
+/// The page size is in device independent pixels, which are 1/96 inch.
+const DIPS_PER_INCH: f32 = 96.0;
+const MM_PER_INCH: f32 = 25.4;
+
+/// A rectangle of a page box in the same unit as [`Size`].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// The boxes of a page specified in the PDF document.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PageDimensions {
+    pub media_box: Bounds,
+    pub crop_box: Bounds,
+    pub bleed_box: Bounds,
+    pub trim_box: Bounds,
+    pub art_box: Bounds,
+}
+
+/// The rotation of a page specified in the PDF document.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PageRotation {
+    #[default]
+    Normal,
+    Rotate90,
+    Rotate180,
+    Rotate270,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -300,16 +637,211 @@ impl Size {
         self.height
     }
 
+    /// The ratio of the width to the height. Returns `0.0` for a degenerate page with zero height, instead of infinity or NaN.
     pub fn aspect_ratio(&self) -> f32 {
+        if self.height == 0.0 {
+            return 0.0;
+        }
         self.width() / self.height()
     }
+
+    /// The width in inches.
+    pub fn width_inches(&self) -> f32 {
+        self.width / DIPS_PER_INCH
+    }
+
+    /// The height in inches.
+    pub fn height_inches(&self) -> f32 {
+        self.height / DIPS_PER_INCH
+    }
+
+    /// The width in millimeters.
+    pub fn width_mm(&self) -> f32 {
+        self.width_inches() * MM_PER_INCH
+    }
+
+    /// The height in millimeters.
+    pub fn height_mm(&self) -> f32 {
+        self.height_inches() * MM_PER_INCH
+    }
+
+    /// The height scaled to `width`, preserving the aspect ratio. Returns `0.0` for a degenerate size with zero width.
+    pub fn scale_to_width(&self, width: f32) -> f32 {
+        if self.width == 0.0 {
+            return 0.0;
+        }
+        width * self.height / self.width
+    }
+
+    /// The width scaled to `height`, preserving the aspect ratio. Returns `0.0` for a degenerate size with zero height.
+    pub fn scale_to_height(&self, height: f32) -> f32 {
+        height * self.aspect_ratio()
+    }
 }
 
-impl From<Foundation::Size> for Size {
-    fn from(value: Foundation::Size) -> Self {
+impl Mul<f32> for Size {
+    type Output = Self;
+    fn mul(self, rhs: f32) -> Self::Output {
         Self {
-            width: value.Width,
-            height: value.Height,
+            width: self.width * rhs,
+            height: self.height * rhs,
         }
     }
 }
+
+impl Div<f32> for Size {
+    type Output = Self;
+    fn div(self, rhs: f32) -> Self::Output {
+        Self {
+            width: self.width / rhs,
+            height: self.height / rhs,
+        }
+    }
+}
+
+impl MulAssign<f32> for Size {
+    fn mul_assign(&mut self, rhs: f32) {
+        *self = *self * rhs;
+    }
+}
+
+impl DivAssign<f32> for Size {
+    fn div_assign(&mut self, rhs: f32) {
+        *self = *self / rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(width: f32, height: f32) -> Size {
+        Size { width, height }
+    }
+
+    #[test]
+    fn aspect_ratio() {
+        assert_eq!(size(200.0, 100.0).aspect_ratio(), 2.0);
+        assert_eq!(size(200.0, 0.0).aspect_ratio(), 0.0);
+        assert_eq!(size(0.0, 100.0).aspect_ratio(), 0.0);
+        assert_eq!(size(0.0, 0.0).aspect_ratio(), 0.0);
+    }
+
+    #[test]
+    fn operators() {
+        let s = size(200.0, 100.0) * 1.5;
+        assert_eq!((s.width(), s.height()), (300.0, 150.0));
+        let s = size(200.0, 100.0) / 4.0;
+        assert_eq!((s.width(), s.height()), (50.0, 25.0));
+        let mut s = size(200.0, 100.0);
+        s *= 0.5;
+        assert_eq!((s.width(), s.height()), (100.0, 50.0));
+        s /= 2.0;
+        assert_eq!((s.width(), s.height()), (50.0, 25.0));
+        let s = size(612.0, 792.0) * 0.25;
+        assert_eq!(s.aspect_ratio(), size(612.0, 792.0).aspect_ratio());
+    }
+
+    #[test]
+    fn scale_to_width_and_height() {
+        let s = size(612.0, 792.0);
+        assert_eq!(s.scale_to_width(306.0), 396.0);
+        assert_eq!(s.scale_to_height(396.0), 306.0);
+        assert_eq!(s.scale_to_height(s.scale_to_width(153.0)), 153.0);
+        assert_eq!(size(0.0, 100.0).scale_to_width(50.0), 0.0);
+        assert_eq!(size(100.0, 0.0).scale_to_height(50.0), 0.0);
+        assert_eq!(size(0.0, 100.0).scale_to_height(50.0), 0.0);
+        assert_eq!(size(100.0, 0.0).scale_to_width(50.0), 0.0);
+    }
+
+    fn rendered(options: Options) -> (f32, f32) {
+        let s = options.rendered_size(size(612.0, 792.0));
+        (s.width(), s.height())
+    }
+
+    #[test]
+    fn rendered_size() {
+        assert_eq!(rendered(Options::default()), (612.0, 792.0));
+        assert_eq!(
+            rendered(Options::builder().width(306).build()),
+            (306.0, 396.0)
+        );
+        assert_eq!(
+            rendered(Options::builder().height(396).build()),
+            (306.0, 396.0)
+        );
+        assert_eq!(
+            rendered(Options::builder().width(100).height(100).build()),
+            (100.0, 100.0)
+        );
+        assert_eq!(
+            rendered(
+                Options::builder()
+                    .width(300)
+                    .height(300)
+                    .scale_mode(ScaleMode::Fit)
+                    .build()
+            ),
+            (232.0, 300.0)
+        );
+        assert_eq!(
+            rendered(
+                Options::builder()
+                    .width(300)
+                    .height(300)
+                    .scale_mode(ScaleMode::Fill)
+                    .build()
+            ),
+            (300.0, 300.0)
+        );
+        assert_eq!(
+            rendered(
+                Options::builder()
+                    .width(306)
+                    .height(100)
+                    .preserve_source_aspect(true)
+                    .build()
+            ),
+            (306.0, 396.0)
+        );
+        assert_eq!(
+            rendered(Options::builder().dpi(48.0).build()),
+            (306.0, 396.0)
+        );
+        assert_eq!(
+            rendered(
+                Options::builder()
+                    .rect(Rect::new(100, 100, 192, 96))
+                    .dpi(48.0)
+                    .build()
+            ),
+            (96.0, 48.0)
+        );
+        assert_eq!(
+            rendered(Options::builder().rect(Rect::new(0, 0, 100, 50)).build()),
+            (100.0, 50.0)
+        );
+        assert_eq!(
+            rendered(
+                Options::builder()
+                    .rect(Rect::new(10, 10, 100, 50))
+                    .width(200)
+                    .build()
+            ),
+            (200.0, 100.0)
+        );
+        assert_eq!(
+            rendered(Options::builder().width(306).rotate(Rotation::Cw90).build()),
+            (396.0, 306.0)
+        );
+        assert_eq!(
+            rendered(
+                Options::builder()
+                    .width(306)
+                    .rotate(Rotation::Cw180)
+                    .build()
+            ),
+            (306.0, 396.0)
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use crate::PdfThumbError;
+
+/// Map page indices `0..count` with `f` on up to `workers` threads, collecting the results in page order.
+#[cfg(feature = "rayon")]
+pub fn map<T, F>(count: u32, workers: usize, f: F) -> Result<Vec<T>, PdfThumbError>
+where
+    T: Send,
+    F: Fn(u32) -> Result<T, PdfThumbError> + Send + Sync,
+{
+    use rayon::prelude::*;
+
+    let workers = workers.clamp(1, count.max(1) as usize);
+    pool(workers)?.install(|| (0..count).into_par_iter().map(f).collect())
+}
+
+/// The thread pool of `threads` threads. Only the pool of the last thread count is kept for reuse, so varying the count never accumulates pools.
+#[cfg(feature = "rayon")]
+fn pool(threads: usize) -> Result<std::sync::Arc<rayon::ThreadPool>, PdfThumbError> {
+    use std::sync::{Arc, Mutex};
+
+    static POOL: Mutex<Option<Arc<rayon::ThreadPool>>> = Mutex::new(None);
+    let mut pool = POOL.lock().unwrap_or_else(|e| e.into_inner());
+    match &*pool {
+        Some(pool) if pool.current_num_threads() == threads => Ok(pool.clone()),
+        _ => {
+            let new = Arc::new(
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()?,
+            );
+            *pool = Some(new.clone());
+            Ok(new)
+        }
+    }
+}
+
+/// Map page indices `0..count` with `f` on up to `workers` threads, collecting the results in page order.
+#[cfg(not(feature = "rayon"))]
+pub fn map<T, F>(count: u32, workers: usize, f: F) -> Result<Vec<T>, PdfThumbError>
+where
+    T: Send,
+    F: Fn(u32) -> Result<T, PdfThumbError> + Send + Sync,
+{
+    use std::{
+        panic,
+        sync::atomic::{AtomicU32, Ordering},
+        thread,
+    };
+
+    let next = AtomicU32::new(0);
+    let workers = workers.clamp(1, count.max(1) as usize);
+    let mut results = thread::scope(|s| {
+        let handles = (0..workers)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let page = next.fetch_add(1, Ordering::Relaxed);
+                        if page >= count {
+                            break results;
+                        }
+                        results.push((page, f(page)));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect::<Vec<_>>()
+    });
+    results.sort_by_key(|(page, _)| *page);
+    results.into_iter().map(|(_, result)| result).collect()
+}
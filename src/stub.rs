@@ -0,0 +1,355 @@
+//! The fallback of the public API on platforms other than Windows, where every operation fails with [`PdfThumbError::Unsupported`].
+
+#![allow(unused_variables)]
+
+use crate::{
+    Bounds, CancellationToken, ImageFormat, Options, PageDimensions, PageRotation, PdfThumbError,
+    Size,
+};
+use std::{
+    io::{Read, Write},
+    ops::Range,
+    path::Path,
+    time::Duration,
+};
+
+/// A PDF document. Rendering is only supported on Windows, so a document can not be loaded on this platform.
+#[derive(Debug, Clone)]
+pub struct PdfDocument {
+    _private: (),
+}
+
+impl PdfDocument {
+    pub fn load(pdf: &[u8]) -> Result<Self, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub async fn load_async(pdf: &[u8]) -> Result<Self, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn load_with_password(pdf: &[u8], password: &str) -> Result<Self, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub async fn load_with_password_async(
+        pdf: &[u8],
+        password: &str,
+    ) -> Result<Self, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    #[cfg(feature = "tokio")]
+    pub async fn load_from_reader_async<R: tokio::io::AsyncRead + Unpin>(
+        reader: R,
+    ) -> Result<Self, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn from_bytes(pdf: &[u8]) -> Result<Self, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    #[cfg(feature = "tokio")]
+    pub async fn from_async_reader<R: tokio::io::AsyncRead + Unpin>(
+        reader: R,
+    ) -> Result<Self, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub async fn open_async<P: AsRef<Path>>(path: P) -> Result<Self, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub async fn open_async_timeout<P: AsRef<Path>>(
+        path: P,
+        timeout: Duration,
+    ) -> Result<Self, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn open_with_password<P: AsRef<Path>>(
+        path: P,
+        password: &str,
+    ) -> Result<Self, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub async fn open_with_password_async<P: AsRef<Path>>(
+        path: P,
+        password: &str,
+    ) -> Result<Self, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    #[cfg(feature = "url")]
+    pub async fn open_url_async(url: &str) -> Result<Self, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn page_count(&self) -> Result<u32, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn is_password_protected(&self) -> Result<bool, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn thumb(&self) -> Result<Vec<u8>, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub async fn thumb_async(&self) -> Result<Vec<u8>, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn thumb_with_options(&self, options: Options) -> Result<Vec<u8>, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn thumb_first_page(&self, options: Options) -> Result<Option<Vec<u8>>, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn thumb_with_options_timeout(
+        &self,
+        options: Options,
+        timeout: Duration,
+    ) -> Result<Vec<u8>, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub async fn thumb_with_options_async(
+        &self,
+        options: Options,
+    ) -> Result<Vec<u8>, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub async fn thumb_with_options_cancellable_async(
+        &self,
+        options: Options,
+        token: CancellationToken,
+    ) -> Result<Vec<u8>, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn thumb_fit(
+        &self,
+        page: u32,
+        max_width: u32,
+        max_height: u32,
+        format: ImageFormat,
+    ) -> Result<Vec<u8>, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn thumb_with_dimensions(
+        &self,
+        options: Options,
+    ) -> Result<(Vec<u8>, u32, u32), PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn thumb_with_size(&self, options: Options) -> Result<(Vec<u8>, Size), PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn thumb_to_writer<W: Write>(
+        &self,
+        writer: &mut W,
+        options: Options,
+    ) -> Result<u64, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn thumb_to_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: Options,
+    ) -> Result<(), PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    #[cfg(feature = "tokio")]
+    pub async fn thumb_to_file_async<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: Options,
+    ) -> Result<(), PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn thumb_all(&self, options: Options) -> Result<Vec<Vec<u8>>, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    #[cfg(feature = "futures")]
+    pub fn thumb_stream(
+        &self,
+        options: Options,
+    ) -> impl futures::Stream<Item = Result<(u32, Vec<u8>), PdfThumbError>> + '_ {
+        futures::stream::iter([Err(PdfThumbError::Unsupported)])
+    }
+
+    pub fn thumb_all_with_progress(
+        &self,
+        options: Options,
+        progress: impl FnMut(u32, u32),
+    ) -> Result<Vec<Vec<u8>>, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn thumb_range(
+        &self,
+        range: Range<u32>,
+        options: Options,
+    ) -> Result<Vec<Vec<u8>>, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn thumb_all_parallel(
+        &self,
+        options: Options,
+        max_concurrency: usize,
+    ) -> Result<Vec<Vec<u8>>, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub async fn thumb_all_async(&self, options: Options) -> Result<Vec<Vec<u8>>, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    #[cfg(feature = "image")]
+    pub fn thumb_image(&self, options: Options) -> Result<image::DynamicImage, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn contact_sheet(
+        &self,
+        columns: u32,
+        thumb_width: u32,
+        format: ImageFormat,
+    ) -> Result<Vec<u8>, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn render_rgba(&self, options: Options) -> Result<(Vec<u8>, u32, u32), PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn thumb_rgba(&self, options: Options) -> Result<(Vec<u8>, u32, u32), PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn get_page(&self, page_index: u32) -> Result<PdfPage, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn page_sizes(&self) -> Result<Vec<Size>, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn pages(&self) -> impl Iterator<Item = Result<PdfPage, PdfThumbError>> + '_ {
+        std::iter::once(Err(PdfThumbError::Unsupported))
+    }
+}
+
+/// A page of a PDF document. Rendering is only supported on Windows, so a page can not be obtained on this platform.
+#[derive(Debug)]
+pub struct PdfPage {
+    _private: (),
+}
+
+impl PdfPage {
+    pub fn thumb(&self, options: Options) -> Result<Vec<u8>, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub async fn thumb_async(&self, options: Options) -> Result<Vec<u8>, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn prepare(&self) -> Result<(), PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub async fn prepare_async(&self) -> Result<(), PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn index(&self) -> Result<u32, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn size(&self) -> Result<Size, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn preferred_zoom(&self) -> Result<f32, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn dimensions(&self) -> Result<PageDimensions, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn media_box(&self) -> Result<Bounds, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn crop_box(&self) -> Result<Bounds, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn bleed_box(&self) -> Result<Bounds, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn trim_box(&self) -> Result<Bounds, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn art_box(&self) -> Result<Bounds, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn rotation(&self) -> Result<PageRotation, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+}
+
+#[derive(Debug)]
+pub struct Renderer {
+    _private: (),
+}
+
+impl Renderer {
+    pub fn new() -> Result<Self, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn with_options(options: Options) -> Result<Self, PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn render(&mut self, page: &PdfPage, options: Options) -> Result<&[u8], PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+
+    pub fn render_page(&mut self, page: &PdfPage) -> Result<&[u8], PdfThumbError> {
+        Err(PdfThumbError::Unsupported)
+    }
+}
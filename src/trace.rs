@@ -0,0 +1,16 @@
+use std::time::Instant;
+
+/// Emit the elapsed time at debug level in the current span when dropped, so failed renders are timed too.
+pub struct Elapsed(Instant);
+
+impl Elapsed {
+    pub fn start() -> Self {
+        Self(Instant::now())
+    }
+}
+
+impl Drop for Elapsed {
+    fn drop(&mut self) {
+        tracing::debug!(elapsed = ?self.0.elapsed(), "render finished");
+    }
+}
@@ -0,0 +1,47 @@
+use crate::{bmp, encoder, win::read_bytes, Options, PdfThumbError};
+use image::{codecs::webp::WebPEncoder, ExtendedColorType, GrayImage};
+use windows::Storage::Streams::InMemoryRandomAccessStream;
+
+/// Encode the lossless image of `input` into WebP in `output`, applying the rotation of the options.
+pub fn encode(
+    input: &InMemoryRandomAccessStream,
+    output: &InMemoryRandomAccessStream,
+    options: &Options,
+) -> Result<(), PdfThumbError> {
+    let webp = to_webp(&read_bytes(input.clone())?, options)?;
+    encoder::write(output, &webp)
+}
+
+/// Encode the lossless image of `input` into WebP in `output` asynchronously, applying the rotation of the options.
+pub async fn encode_async(
+    input: &InMemoryRandomAccessStream,
+    output: &InMemoryRandomAccessStream,
+    options: &Options,
+) -> Result<(), PdfThumbError> {
+    let webp = to_webp(&read_bytes(input.clone())?, options)?;
+    encoder::write_async(output, &webp).await
+}
+
+/// Encode tightly packed RGBA pixels into lossless WebP.
+pub fn encode_pixels(pixels: &[u8], width: u32, height: u32) -> Result<Vec<u8>, PdfThumbError> {
+    let mut buf = Vec::new();
+    WebPEncoder::new_lossless(&mut buf).encode(pixels, width, height, ExtendedColorType::Rgba8)?;
+    Ok(buf)
+}
+
+/// Encode a grayscale image into lossless WebP.
+pub fn encode_gray(image: &GrayImage) -> Result<Vec<u8>, PdfThumbError> {
+    let mut buf = Vec::new();
+    WebPEncoder::new_lossless(&mut buf).encode(
+        image.as_raw(),
+        image.width(),
+        image.height(),
+        ExtendedColorType::L8,
+    )?;
+    Ok(buf)
+}
+
+fn to_webp(bmp: &[u8], options: &Options) -> Result<Vec<u8>, PdfThumbError> {
+    let image = bmp::to_image(bmp, options.rotate)?;
+    encode_pixels(image.as_raw(), image.width(), image.height())
+}
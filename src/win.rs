@@ -0,0 +1,1704 @@
+#[cfg(feature = "image")]
+use crate::gray;
+#[cfg(feature = "tracing")]
+use crate::trace;
+use crate::{
+    bmp,
+    cancel::{self, CancellationToken},
+    encoder,
+    guid::*,
+    parallel, Bounds, Color, ImageFormat, Options, PageDimensions, PageRotation, PdfThumbError,
+    Rect, ScaleMode, Size,
+};
+use std::{
+    ffi::OsString,
+    io::{ErrorKind, Read, Write},
+    ops::{Deref, Range},
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+#[cfg(feature = "url")]
+use windows::Storage::Streams::RandomAccessStreamReference;
+use windows::{
+    core::{RuntimeType, GUID, HRESULT, HSTRING},
+    Data::Pdf::{
+        PdfDocument as PdfDocument_, PdfPage as PdfPage_, PdfPageDimensions, PdfPageRenderOptions,
+        PdfPageRotation,
+    },
+    Foundation,
+    Storage::{
+        StorageFile,
+        Streams::{DataReader, DataWriter, InMemoryRandomAccessStream},
+    },
+    UI,
+};
+use windows_future::{IAsyncAction, IAsyncOperation};
+
+impl PdfThumbError {
+    fn at_page(self, index: u32) -> Self {
+        Self::Page {
+            index,
+            source: Box::new(self),
+        }
+    }
+}
+
+/// `HRESULT_FROM_WIN32(ERROR_WRONG_PASSWORD)`, returned when a PDF document can not be decrypted.
+const E_WRONG_PASSWORD: HRESULT = HRESULT(0x8007052B_u32 as _);
+
+fn password_error(e: windows::core::Error, password: &str) -> PdfThumbError {
+    match e.code() {
+        E_WRONG_PASSWORD if password.is_empty() => PdfThumbError::PasswordRequired,
+        E_WRONG_PASSWORD => PdfThumbError::WrongPassword,
+        _ => e.into(),
+    }
+}
+
+/// `HRESULT_FROM_WIN32(ERROR_FILE_NOT_FOUND)` and `HRESULT_FROM_WIN32(ERROR_PATH_NOT_FOUND)`, returned when a file does not exist.
+const E_FILE_NOT_FOUND: HRESULT = HRESULT(0x80070002_u32 as _);
+const E_PATH_NOT_FOUND: HRESULT = HRESULT(0x80070003_u32 as _);
+
+/// `E_ACCESSDENIED`, returned when a file can not be accessed.
+const E_ACCESSDENIED: HRESULT = HRESULT(0x80070005_u32 as _);
+
+fn file_error(e: windows::core::Error, path: &Path) -> PdfThumbError {
+    match e.code() {
+        E_FILE_NOT_FOUND | E_PATH_NOT_FOUND => PdfThumbError::FileNotFound(path.to_path_buf()),
+        E_ACCESSDENIED => PdfThumbError::AccessDenied(path.to_path_buf()),
+        _ => e.into(),
+    }
+}
+
+/// `WINCODEC_ERR_COMPONENTNOTFOUND`, returned when the encoder of the image format is not installed.
+const WINCODEC_ERR_COMPONENTNOTFOUND: HRESULT = HRESULT(0x88982F50_u32 as _);
+
+/// `MF_E_TOPO_CODEC_NOT_FOUND`, returned when the HEVC codec extension for HEIF is not installed.
+const MF_E_TOPO_CODEC_NOT_FOUND: HRESULT = HRESULT(0xC00D5212_u32 as _);
+
+pub(crate) fn codec_error(e: windows::core::Error) -> PdfThumbError {
+    match e.code() {
+        WINCODEC_ERR_COMPONENTNOTFOUND => PdfThumbError::UnsupportedFormat,
+        MF_E_TOPO_CODEC_NOT_FOUND => PdfThumbError::CodecNotInstalled,
+        _ => e.into(),
+    }
+}
+
+impl Rect {
+    /// Whether the rect lies within the specified size.
+    fn is_within(&self, size: Size) -> bool {
+        self.x as f32 + self.width as f32 <= size.width()
+            && self.y as f32 + self.height as f32 <= size.height()
+    }
+
+    /// Shrink the rect to lie within the specified size.
+    fn clamp_to(self, size: Size) -> Self {
+        let (width, height) = (size.width() as u32, size.height() as u32);
+        let x = self.x.min(width);
+        let y = self.y.min(height);
+        Self {
+            x,
+            y,
+            width: self.width.min(width - x),
+            height: self.height.min(height - y),
+        }
+    }
+}
+
+impl From<Rect> for Foundation::Rect {
+    fn from(r: Rect) -> Self {
+        Self {
+            X: r.x as _,
+            Y: r.y as _,
+            Width: r.width as _,
+            Height: r.height as _,
+        }
+    }
+}
+
+impl From<Color> for UI::Color {
+    fn from(c: Color) -> Self {
+        Self {
+            A: c.a,
+            R: c.r,
+            G: c.g,
+            B: c.b,
+        }
+    }
+}
+
+impl Options {
+    /// Resolve the options depending on the page to be rendered.
+    fn resolve(self, page: &PdfPage) -> Result<Self, PdfThumbError> {
+        let page_size = page.size()?;
+        if self.rect.ne(&Rect::default()) {
+            if self.rect.width == 0 || self.rect.height == 0 {
+                return Err(PdfThumbError::InvalidOptions(
+                    "rect must have a non-zero width and height",
+                ));
+            }
+            if !self.rect.is_within(page_size) {
+                return Err(PdfThumbError::RectOutOfBounds {
+                    rect: self.rect,
+                    size: page_size,
+                });
+            }
+        }
+        if self.dpi.is_some() && (self.width > 0 || self.height > 0) {
+            return Err(PdfThumbError::InvalidOptions(
+                "dpi can not be specified with width or height",
+            ));
+        }
+        let source = self.source_size(page_size);
+        let size = self.scaled_size(source);
+        let mut options = Self {
+            width: size.width() as u32,
+            height: size.height() as u32,
+            ..self
+        };
+        if self.scale_mode == ScaleMode::Fill && self.width > 0 && self.height > 0 {
+            let scale = (size.width() / source.width()).max(size.height() / source.height());
+            let (crop_width, crop_height) = (size.width() / scale, size.height() / scale);
+            // Center the crop in the source, then keep it within the page.
+            let rect = Rect {
+                x: self.rect.x + ((source.width() - crop_width) / 2.0).round() as u32,
+                y: self.rect.y + ((source.height() - crop_height) / 2.0).round() as u32,
+                width: crop_width.round() as u32,
+                height: crop_height.round() as u32,
+            };
+            options.rect = rect.clamp_to(page_size);
+            #[cfg(feature = "tracing")]
+            if options.rect != rect {
+                tracing::debug!(?rect, clamped = ?options.rect, "clamped the crop rect to the page");
+            }
+        }
+        Ok(options)
+    }
+
+    /// The options to render an intermediate image which is re-encoded later.
+    fn lossless(self) -> Self {
+        Self {
+            format: Some(ImageFormat::Bmp),
+            ..self
+        }
+    }
+}
+
+impl TryFrom<Options> for PdfPageRenderOptions {
+    type Error = PdfThumbError;
+    fn try_from(options: Options) -> Result<Self, Self::Error> {
+        let op = PdfPageRenderOptions::new()?;
+        if options.width > 0 {
+            op.SetDestinationWidth(options.width)?;
+        }
+        if options.height > 0 {
+            op.SetDestinationHeight(options.height)?;
+        }
+        if options.rect.ne(&Rect::default()) {
+            op.SetSourceRect(options.rect.into())?;
+        }
+        if let Some(background) = options.background {
+            op.SetBackgroundColor(background.into())?;
+        }
+        op.SetIsIgnoringHighContrast(options.ignore_high_contrast)?;
+        op.SetBitmapEncoderId(
+            options
+                .image_format()
+                .guid()
+                .ok_or(PdfThumbError::UnsupportedFormat)?,
+        )?;
+        Ok(op)
+    }
+}
+
+impl ImageFormat {
+    /// The GUID of the WIC encoder of the image format, or `None` if the format is encoded by the `image` crate.
+    pub(crate) const fn guid(&self) -> Option<GUID> {
+        use ImageFormat::*;
+        Some(match self {
+            Png => PNG_ENCORDER_ID,
+            Bmp => BITMAP_ENCODER_ID,
+            Jpeg => JPEG_ENCORDER_ID,
+            Tiff => TIFF_ENCODER_ID,
+            Gif => GIF_ENCODER_ID,
+            Heif => HEIF_ENCODER_ID,
+            Custom(guid) => *guid,
+            #[cfg(feature = "webp")]
+            WebP => return None,
+        })
+    }
+}
+
+/// A PDF document. Cloning is cheap since it only adds a reference to the underlying WinRT object, so clones can render pages on different threads.
+///
+/// The WinRT `PdfDocument` has no `Close` method. Dropping the document releases its reference immediately, and the file is released once the clones and pages of the document are dropped too.
+#[derive(Debug, Clone)]
+pub struct PdfDocument {
+    doc: PdfDocument_,
+}
+
+unsafe impl Send for PdfDocument {}
+unsafe impl Sync for PdfDocument {}
+
+impl PdfDocument {
+    /// Load a PDF document from memory.
+    ///
+    /// This blocks the current thread until the document is loaded, so it must not be called from inside an async runtime's worker thread. Use [`PdfDocument::load_async`] instead.
+    pub fn load(pdf: &[u8]) -> Result<Self, PdfThumbError> {
+        let (stream, writer) = write_bytes(pdf)?;
+        writer.StoreAsync()?.get()?;
+        writer.FlushAsync()?.get()?;
+        writer.DetachStream()?;
+        let doc = PdfDocument_::LoadFromStreamAsync(&stream)?
+            .get()
+            .map_err(|e| password_error(e, ""))?;
+        Ok(Self { doc })
+    }
+
+    /// Load a PDF document from memory asynchronously.
+    pub async fn load_async(pdf: &[u8]) -> Result<Self, PdfThumbError> {
+        let (stream, writer) = write_bytes(pdf)?;
+        writer.StoreAsync()?.await?;
+        writer.FlushAsync()?.await?;
+        writer.DetachStream()?;
+        let doc = PdfDocument_::LoadFromStreamAsync(&stream)?
+            .await
+            .map_err(|e| password_error(e, ""))?;
+        Ok(Self { doc })
+    }
+
+    /// Load a password protected PDF document from memory.
+    ///
+    /// An empty `password` means no password. If the document is not encrypted, `password` is ignored.
+    pub fn load_with_password(pdf: &[u8], password: &str) -> Result<Self, PdfThumbError> {
+        let (stream, writer) = write_bytes(pdf)?;
+        writer.StoreAsync()?.get()?;
+        writer.FlushAsync()?.get()?;
+        writer.DetachStream()?;
+        let doc = load_with_password(&stream, password)?
+            .get()
+            .map_err(|e| password_error(e, password))?;
+        Ok(Self { doc })
+    }
+
+    /// Load a password protected PDF document from memory asynchronously.
+    ///
+    /// An empty `password` means no password. If the document is not encrypted, `password` is ignored.
+    pub async fn load_with_password_async(
+        pdf: &[u8],
+        password: &str,
+    ) -> Result<Self, PdfThumbError> {
+        let (stream, writer) = write_bytes(pdf)?;
+        writer.StoreAsync()?.await?;
+        writer.FlushAsync()?.await?;
+        writer.DetachStream()?;
+        let doc = load_with_password(&stream, password)?
+            .await
+            .map_err(|e| password_error(e, password))?;
+        Ok(Self { doc })
+    }
+
+    /// Load a PDF document from a reader. The contents are copied into the document stream in chunks, so the whole document is not buffered up front.
+    ///
+    /// This blocks the current thread until the document is loaded, like [`PdfDocument::load`].
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, PdfThumbError> {
+        let stream = InMemoryRandomAccessStream::new()?;
+        let writer = DataWriter::CreateDataWriter(&stream)?;
+        let mut buf = vec![0; CHUNK_SIZE as usize];
+        loop {
+            let len = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            writer.WriteBytes(&buf[..len])?;
+            writer.StoreAsync()?.get()?;
+        }
+        writer.FlushAsync()?.get()?;
+        writer.DetachStream()?;
+        let doc = PdfDocument_::LoadFromStreamAsync(&stream)?
+            .get()
+            .map_err(|e| password_error(e, ""))?;
+        Ok(Self { doc })
+    }
+
+    /// Load a PDF document from an async reader. The contents are copied into the document stream in chunks.
+    #[cfg(feature = "tokio")]
+    pub async fn load_from_reader_async<R: tokio::io::AsyncRead + Unpin>(
+        mut reader: R,
+    ) -> Result<Self, PdfThumbError> {
+        use tokio::io::AsyncReadExt;
+
+        let stream = InMemoryRandomAccessStream::new()?;
+        let writer = DataWriter::CreateDataWriter(&stream)?;
+        let mut buf = vec![0; CHUNK_SIZE as usize];
+        loop {
+            let len = reader.read(&mut buf).await?;
+            if len == 0 {
+                break;
+            }
+            writer.WriteBytes(&buf[..len])?;
+            writer.StoreAsync()?.await?;
+        }
+        writer.FlushAsync()?.await?;
+        writer.DetachStream()?;
+        let doc = PdfDocument_::LoadFromStreamAsync(&stream)?
+            .await
+            .map_err(|e| password_error(e, ""))?;
+        Ok(Self { doc })
+    }
+
+    /// Load a PDF document from memory. Same as [`PdfDocument::load`].
+    pub fn from_bytes(pdf: &[u8]) -> Result<Self, PdfThumbError> {
+        Self::load(pdf)
+    }
+
+    /// Load a PDF document from an async reader. Same as [`PdfDocument::load_from_reader_async`].
+    #[cfg(feature = "tokio")]
+    pub async fn from_async_reader<R: tokio::io::AsyncRead + Unpin>(
+        reader: R,
+    ) -> Result<Self, PdfThumbError> {
+        Self::load_from_reader_async(reader).await
+    }
+
+    /// Open a PDF document from a path. Same as [`PdfDocument::open`].
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, PdfThumbError> {
+        Self::open(path)
+    }
+
+    /// Open a PDF document from a path.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, PdfThumbError> {
+        let file = get_file(&path)?
+            .get()
+            .map_err(|e| file_error(e, path.as_ref()))?;
+        let doc = open(&file)?.get().map_err(|e| password_error(e, ""))?;
+        Ok(Self { doc })
+    }
+
+    /// Open a PDF document from a path asynchronously.
+    pub async fn open_async<P: AsRef<Path>>(path: P) -> Result<Self, PdfThumbError> {
+        let file = get_file(&path)?
+            .await
+            .map_err(|e| file_error(e, path.as_ref()))?;
+        let doc = open(&file)?.await.map_err(|e| password_error(e, ""))?;
+        Ok(Self { doc })
+    }
+
+    /// Open a password protected PDF document from a path.
+    ///
+    /// An empty `password` means no password. If the document is not encrypted, `password` is ignored.
+    pub fn open_with_password<P: AsRef<Path>>(
+        path: P,
+        password: &str,
+    ) -> Result<Self, PdfThumbError> {
+        let file = get_file(&path)?
+            .get()
+            .map_err(|e| file_error(e, path.as_ref()))?;
+        let doc = open_with_password(&file, password)?
+            .get()
+            .map_err(|e| password_error(e, password))?;
+        Ok(Self { doc })
+    }
+
+    /// Open a password protected PDF document from a path asynchronously.
+    ///
+    /// An empty `password` means no password. If the document is not encrypted, `password` is ignored.
+    pub async fn open_with_password_async<P: AsRef<Path>>(
+        path: P,
+        password: &str,
+    ) -> Result<Self, PdfThumbError> {
+        let file = get_file(&path)?
+            .await
+            .map_err(|e| file_error(e, path.as_ref()))?;
+        let doc = open_with_password(&file, password)?
+            .await
+            .map_err(|e| password_error(e, password))?;
+        Ok(Self { doc })
+    }
+
+    /// Open a PDF document from a path asynchronously, giving up when it is not opened within `timeout`.
+    ///
+    /// Returns [`PdfThumbError::Timeout`] after cancelling the in-flight operation.
+    pub async fn open_async_timeout<P: AsRef<Path>>(
+        path: P,
+        timeout: Duration,
+    ) -> Result<Self, PdfThumbError> {
+        let token = CancellationToken::new();
+        token.cancel_at(Instant::now() + timeout);
+        let file = until_cancelled(get_file(&path)?, &token)
+            .await
+            .ok_or(PdfThumbError::Timeout)?
+            .map_err(|e| file_error(e, path.as_ref()))?;
+        let doc = until_cancelled(open(&file)?, &token)
+            .await
+            .ok_or(PdfThumbError::Timeout)?
+            .map_err(|e| password_error(e, ""))?;
+        Ok(Self { doc })
+    }
+
+    /// Open a PDF document from a WinRT `StorageFile`, like one picked by the user in a UWP or WinUI app.
+    pub fn open_from_storage_file(file: &StorageFile) -> Result<Self, PdfThumbError> {
+        let doc = open(file)?.get().map_err(|e| password_error(e, ""))?;
+        Ok(Self { doc })
+    }
+
+    /// Open a PDF document from a WinRT `StorageFile` asynchronously.
+    pub async fn open_from_storage_file_async(file: &StorageFile) -> Result<Self, PdfThumbError> {
+        let doc = open(file)?.await.map_err(|e| password_error(e, ""))?;
+        Ok(Self { doc })
+    }
+
+    /// Download and load a PDF document from a URL asynchronously.
+    ///
+    /// A failure to fetch the document is returned as [`PdfThumbError::Network`].
+    #[cfg(feature = "url")]
+    pub async fn open_url_async(url: &str) -> Result<Self, PdfThumbError> {
+        let uri = Foundation::Uri::CreateUri(&HSTRING::from(url))?;
+        let stream = RandomAccessStreamReference::CreateFromUri(&uri)?
+            .OpenReadAsync()
+            .map_err(PdfThumbError::Network)?
+            .await
+            .map_err(PdfThumbError::Network)?;
+        let doc = PdfDocument_::LoadFromStreamAsync(&stream)?
+            .await
+            .map_err(|e| password_error(e, ""))?;
+        Ok(Self { doc })
+    }
+
+    /// Get the number of PDF document.
+    pub fn page_count(&self) -> Result<u32, PdfThumbError> {
+        Ok(self.doc.PageCount()?)
+    }
+
+    /// Get a reference to the underlying WinRT `PdfDocument`.
+    pub fn as_inner(&self) -> &PdfDocument_ {
+        &self.doc
+    }
+
+    /// Unwrap the underlying WinRT `PdfDocument`.
+    pub fn into_inner(self) -> PdfDocument_ {
+        self.doc
+    }
+
+    /// Get whether the PDF document is password protected.
+    pub fn is_password_protected(&self) -> Result<bool, PdfThumbError> {
+        Ok(self.doc.IsPasswordProtected()?)
+    }
+
+    /// Generate a thumbnail image with default options.
+    pub fn thumb(&self) -> Result<Vec<u8>, PdfThumbError> {
+        let options = Options::default();
+        self.thumb_with_options(options)
+    }
+
+    /// Generate a thumbnail image with default options asynchronously.
+    pub async fn thumb_async(&self) -> Result<Vec<u8>, PdfThumbError> {
+        let options = Options::default();
+        self.thumb_with_options_async(options).await
+    }
+
+    /// Generate a thumbnail image with the specified options.
+    pub fn thumb_with_options(&self, options: Options) -> Result<Vec<u8>, PdfThumbError> {
+        let output = self.render_to_stream(options, None)?;
+        read_bytes(output)
+    }
+
+    /// Generate a thumbnail image of the first page with the specified options, or `None` if the document has no pages. `page` of the options is ignored.
+    pub fn thumb_first_page(&self, options: Options) -> Result<Option<Vec<u8>>, PdfThumbError> {
+        if self.page_count()? == 0 {
+            return Ok(None);
+        }
+        self.thumb_with_options(Options { page: 0, ..options })
+            .map(Some)
+    }
+
+    /// Generate a thumbnail image with the specified options, giving up when it is not generated within `timeout`.
+    ///
+    /// The deadline covers the whole operation, including re-encoding the image. At the deadline the pending WinRT operation, like the render or a step of re-encoding, is cancelled and [`PdfThumbError::Timeout`] is returned.
+    pub fn thumb_with_options_timeout(
+        &self,
+        options: Options,
+        timeout: Duration,
+    ) -> Result<Vec<u8>, PdfThumbError> {
+        let token = CancellationToken::new();
+        token.cancel_at(Instant::now() + timeout);
+        self.render_to_stream(options, Some(&token))
+            .and_then(read_bytes)
+            .map_err(|e| match token.is_cancelled() {
+                true => PdfThumbError::Timeout,
+                false => e,
+            })
+    }
+
+    /// Generate a thumbnail image with the specified options asynchronously.
+    pub async fn thumb_with_options_async(
+        &self,
+        options: Options,
+    ) -> Result<Vec<u8>, PdfThumbError> {
+        let output = self.render_to_stream_async(options, None).await?;
+        read_bytes(output)
+    }
+
+    /// Generate a thumbnail image with the specified options asynchronously, which can be cancelled by `token`.
+    ///
+    /// Returns [`PdfThumbError::Cancelled`] when the token is cancelled. Cancellation is best-effort once rendering has started.
+    pub async fn thumb_with_options_cancellable_async(
+        &self,
+        options: Options,
+        token: CancellationToken,
+    ) -> Result<Vec<u8>, PdfThumbError> {
+        let output = self.render_to_stream_async(options, Some(&token)).await?;
+        read_bytes(output)
+    }
+
+    /// Generate a thumbnail image of the page which fits within `max_width` and `max_height`, preserving the aspect ratio.
+    pub fn thumb_fit(
+        &self,
+        page: u32,
+        max_width: u32,
+        max_height: u32,
+        format: ImageFormat,
+    ) -> Result<Vec<u8>, PdfThumbError> {
+        let options = Options {
+            width: max_width,
+            height: max_height,
+            page,
+            format: Some(format),
+            scale_mode: ScaleMode::Fit,
+            ..Default::default()
+        };
+        self.thumb_with_options(options)
+    }
+
+    /// Generate a thumbnail image with the specified options, returning it with the width and height of the image.
+    pub fn thumb_with_dimensions(
+        &self,
+        options: Options,
+    ) -> Result<(Vec<u8>, u32, u32), PdfThumbError> {
+        let output = self.render_to_stream(options, None)?;
+        let (width, height) = encoder::dimensions(&output)?;
+        Ok((read_bytes(output)?, width, height))
+    }
+
+    /// Generate a thumbnail image with the specified options, returning it with the pixel size of the image.
+    pub fn thumb_with_size(&self, options: Options) -> Result<(Vec<u8>, Size), PdfThumbError> {
+        let (buf, width, height) = self.thumb_with_dimensions(options)?;
+        let size = Size {
+            width: width as f32,
+            height: height as f32,
+        };
+        Ok((buf, size))
+    }
+
+    /// Generate a thumbnail image with the specified options and write it to `writer`, returning the number of bytes written.
+    pub fn thumb_to_writer<W: Write>(
+        &self,
+        writer: &mut W,
+        options: Options,
+    ) -> Result<u64, PdfThumbError> {
+        let output = self.render_to_stream(options, None)?;
+        read_to(output, writer)
+    }
+
+    fn render_to_stream(
+        &self,
+        options: Options,
+        token: Option<&CancellationToken>,
+    ) -> Result<InMemoryRandomAccessStream, PdfThumbError> {
+        self.get_page(options.page)?
+            .render_to_stream(options, token)
+    }
+
+    async fn render_to_stream_async(
+        &self,
+        options: Options,
+        token: Option<&CancellationToken>,
+    ) -> Result<InMemoryRandomAccessStream, PdfThumbError> {
+        let page = self.get_page(options.page)?;
+        page.render_to_stream_async(options, token).await
+    }
+
+    /// Generate a thumbnail image with the specified options and write it to a file.
+    ///
+    /// If `format` of the options is not specified, the image format is inferred from the extension of `path`, falling back to PNG if the extension is unknown.
+    /// The image is written to a temporary file next to `path` and renamed, so a partially written file is never left at `path`.
+    pub fn thumb_to_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: Options,
+    ) -> Result<(), PdfThumbError> {
+        let format = options.format.or_else(|| ImageFormat::from_path(&path));
+        let thumb = self.thumb_with_options(Options { format, ..options })?;
+        write_atomic(path.as_ref(), &thumb)?;
+        Ok(())
+    }
+
+    /// Generate a thumbnail image with the specified options and write it to a file asynchronously with `tokio::fs`.
+    ///
+    /// The image format is chosen as [`thumb_to_file`](Self::thumb_to_file) does.
+    /// The image is written to a temporary file next to `path` and renamed, so a partially written file is never left at `path`.
+    /// The returned future is `Send`, so it can be spawned.
+    #[cfg(feature = "tokio")]
+    pub async fn thumb_to_file_async<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: Options,
+    ) -> Result<(), PdfThumbError> {
+        let format = options.format.or_else(|| ImageFormat::from_path(&path));
+        let thumb = self
+            .thumb_with_options_async(Options { format, ..options })
+            .await?;
+        write_atomic_async(path.as_ref(), &thumb).await?;
+        Ok(())
+    }
+
+    /// Generate thumbnail images of all pages with the specified options. `page` of the options is ignored.
+    ///
+    /// If a page fails to render, [`PdfThumbError::Page`] with the index of the page is returned.
+    pub fn thumb_all(&self, options: Options) -> Result<Vec<Vec<u8>>, PdfThumbError> {
+        self.thumb_range(0..self.page_count()?, options)
+    }
+
+    /// Generate thumbnail images of all pages as a stream, yielding the page index and the image as each completes. `page` of the options is ignored.
+    #[cfg(feature = "futures")]
+    pub fn thumb_stream(
+        &self,
+        options: Options,
+    ) -> impl futures::Stream<Item = Result<(u32, Vec<u8>), PdfThumbError>> + '_ {
+        use futures::{stream, StreamExt};
+
+        let (count, err) = match self.page_count() {
+            Ok(count) => (count, None),
+            Err(e) => (0, Some(Err(e))),
+        };
+        stream::iter(err).chain(stream::iter(0..count).then(move |page| async move {
+            let thumb = self
+                .thumb_with_options_async(Options { page, ..options })
+                .await
+                .map_err(|e| e.at_page(page))?;
+            Ok((page, thumb))
+        }))
+    }
+
+    /// Generate thumbnail images of all pages with the specified options, calling `progress` with the number of completed pages and the page count after each page. `page` of the options is ignored.
+    ///
+    /// If a page fails to render, [`PdfThumbError::Page`] with the index of the page is returned.
+    pub fn thumb_all_with_progress(
+        &self,
+        options: Options,
+        mut progress: impl FnMut(u32, u32),
+    ) -> Result<Vec<Vec<u8>>, PdfThumbError> {
+        let count = self.page_count()?;
+        (0..count)
+            .map(|page| {
+                let thumb = self
+                    .thumb_with_options(Options { page, ..options })
+                    .map_err(|e| e.at_page(page))?;
+                progress(page + 1, count);
+                Ok(thumb)
+            })
+            .collect()
+    }
+
+    /// Generate thumbnail images of the pages in `range` with the specified options. `page` of the options is ignored.
+    ///
+    /// If `range` exceeds the page count, [`PdfThumbError::PageOutOfRange`] is returned without rendering any page.
+    /// If a page fails to render, [`PdfThumbError::Page`] with the index of the page is returned.
+    pub fn thumb_range(
+        &self,
+        range: Range<u32>,
+        options: Options,
+    ) -> Result<Vec<Vec<u8>>, PdfThumbError> {
+        let count = self.page_count()?;
+        if range.end > count {
+            return Err(PdfThumbError::PageOutOfRange {
+                requested: range.end - 1,
+                count,
+            });
+        }
+        range
+            .map(|page| {
+                self.thumb_with_options(Options { page, ..options })
+                    .map_err(|e| e.at_page(page))
+            })
+            .collect()
+    }
+
+    /// Generate thumbnail images of all pages with the specified options, rendering up to `max_concurrency` pages at once on separate threads. `page` of the options is ignored.
+    ///
+    /// With the `rayon` feature, the pages are rendered on a rayon thread pool of `max_concurrency` threads, capped at the page count. The pool is kept for the next call with the same thread count.
+    ///
+    /// The images are returned in page order. If a page fails to render, [`PdfThumbError::Page`] with the index of the page is returned.
+    pub fn thumb_all_parallel(
+        &self,
+        options: Options,
+        max_concurrency: usize,
+    ) -> Result<Vec<Vec<u8>>, PdfThumbError> {
+        let count = self.page_count()?;
+        parallel::map(count, max_concurrency, |page| {
+            self.thumb_with_options(Options { page, ..options })
+                .map_err(|e| e.at_page(page))
+        })
+    }
+
+    /// Generate thumbnail images of all pages with the specified options asynchronously. `page` of the options is ignored.
+    ///
+    /// If a page fails to render, [`PdfThumbError::Page`] with the index of the page is returned.
+    pub async fn thumb_all_async(&self, options: Options) -> Result<Vec<Vec<u8>>, PdfThumbError> {
+        let mut thumbs = Vec::new();
+        for page in 0..self.page_count()? {
+            let thumb = self
+                .thumb_with_options_async(Options { page, ..options })
+                .await
+                .map_err(|e| e.at_page(page))?;
+            thumbs.push(thumb);
+        }
+        Ok(thumbs)
+    }
+
+    /// Generate a thumbnail image with the specified options as an RGBA image, or a grayscale image if `grayscale` of the options is set. `format` of the options is ignored.
+    ///
+    /// The image is built from the raw pixels of the rendered page, without encoding and decoding it.
+    #[cfg(feature = "image")]
+    pub fn thumb_image(&self, options: Options) -> Result<image::DynamicImage, PdfThumbError> {
+        let (pixels, width, height) = self.render_rgba(options)?;
+        let image = image::RgbaImage::from_raw(width, height, pixels)
+            .ok_or(PdfThumbError::InvalidBitmap)?;
+        if options.grayscale {
+            return Ok(image::imageops::grayscale(&image).into());
+        }
+        Ok(image.into())
+    }
+
+    /// Compose thumbnail images of all pages into a grid of `columns` columns, each `thumb_width` wide, and encode it into `format`.
+    ///
+    /// Cells of the pages which fail to render are left blank. If the document has no pages, [`PdfThumbError::PageOutOfRange`] is returned, and if no page renders, the error of the first page is returned.
+    pub fn contact_sheet(
+        &self,
+        columns: u32,
+        thumb_width: u32,
+        format: ImageFormat,
+    ) -> Result<Vec<u8>, PdfThumbError> {
+        if columns == 0 || thumb_width == 0 {
+            return Err(PdfThumbError::InvalidOptions(
+                "columns and thumb_width must be greater than zero",
+            ));
+        }
+        let options = Options {
+            width: thumb_width,
+            ..Default::default()
+        };
+        let count = self.page_count()?;
+        if count == 0 {
+            return Err(PdfThumbError::PageOutOfRange {
+                requested: 0,
+                count,
+            });
+        }
+        let mut thumbs = (0..count)
+            .map(|page| self.render_rgba(Options { page, ..options }))
+            .collect::<Vec<_>>();
+        if thumbs.iter().all(Result::is_err) {
+            return Err(thumbs.swap_remove(0).unwrap_err());
+        }
+        let cell_height = thumbs.iter().flatten().map(|t| t.2).max().unwrap_or(0);
+        let rows = count.div_ceil(columns);
+        let too_large = || PdfThumbError::InvalidOptions("contact sheet is too large");
+        let width = columns.checked_mul(thumb_width).ok_or_else(too_large)?;
+        let height = rows.checked_mul(cell_height).ok_or_else(too_large)?;
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|len| len.checked_mul(4))
+            .ok_or_else(too_large)?;
+
+        let mut canvas = vec![0xff; len];
+        let stride = width as usize * 4;
+        for (i, (pixels, w, h)) in thumbs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, t)| Some((i, t.as_ref().ok()?)))
+        {
+            let columns = columns as usize;
+            let x = (i % columns) * thumb_width as usize * 4;
+            let y = (i / columns) * cell_height as usize;
+            let src_stride = *w as usize * 4;
+            let len = src_stride.min(thumb_width as usize * 4);
+            for row in 0..*h as usize {
+                let src = row * src_stride;
+                let dst = (y + row) * stride + x;
+                canvas[dst..dst + len].copy_from_slice(&pixels[src..src + len]);
+            }
+        }
+        encoder::encode_pixels(&canvas, width, height, format, None)
+    }
+
+    /// Render a page to tightly packed RGBA bytes with the specified options. `format` of the options is ignored.
+    ///
+    /// Returns the pixels with the width and height of the rendered image, so the length of the pixels is `width * height * 4`.
+    /// Each pixel is 8-bit channels in R, G, B, A order, from the top-left row by row. Alpha is straight, not premultiplied.
+    pub fn render_rgba(&self, options: Options) -> Result<(Vec<u8>, u32, u32), PdfThumbError> {
+        let bmp = self.thumb_with_options(Options {
+            format: Some(ImageFormat::Bmp),
+            #[cfg(feature = "image")]
+            grayscale: false,
+            ..options
+        })?;
+        let (pixels, width, height) = bmp::to_rgba(&bmp)?;
+        #[cfg(feature = "image")]
+        if options.grayscale {
+            return Ok((gray::to_rgba(pixels, width, height)?, width, height));
+        }
+        Ok((pixels, width, height))
+    }
+
+    /// Render a page to tightly packed RGBA bytes with the specified options. Same as [`PdfDocument::render_rgba`].
+    pub fn thumb_rgba(&self, options: Options) -> Result<(Vec<u8>, u32, u32), PdfThumbError> {
+        self.render_rgba(options)
+    }
+
+    pub fn get_page(&self, page_index: u32) -> Result<PdfPage, PdfThumbError> {
+        let count = self.page_count()?;
+        if page_index >= count {
+            return Err(PdfThumbError::PageOutOfRange {
+                requested: page_index,
+                count,
+            });
+        }
+        let page = self.doc.GetPage(page_index)?;
+        Ok(PdfPage::new(page))
+    }
+
+    /// Get the sizes of all pages. Each page is closed as soon as its size is read, even if a later page fails.
+    pub fn page_sizes(&self) -> Result<Vec<Size>, PdfThumbError> {
+        self.pages().map(|page| page?.size()).collect()
+    }
+
+    /// Iterate over the pages lazily. Each page is closed when it is dropped.
+    pub fn pages(&self) -> impl Iterator<Item = Result<PdfPage, PdfThumbError>> + '_ {
+        let (count, err) = match self.page_count() {
+            Ok(count) => (count, None),
+            Err(e) => (0, Some(Err(e))),
+        };
+        err.into_iter()
+            .chain((0..count).map(|page_index| self.get_page(page_index)))
+    }
+}
+
+fn write_bytes(pdf: &[u8]) -> Result<(InMemoryRandomAccessStream, DataWriter), PdfThumbError> {
+    let stream = InMemoryRandomAccessStream::new()?;
+    let writer = DataWriter::CreateDataWriter(&stream)?;
+    writer.WriteBytes(pdf)?;
+    Ok((stream, writer))
+}
+
+fn load_with_password(
+    stream: &InMemoryRandomAccessStream,
+    password: &str,
+) -> Result<IAsyncOperation<PdfDocument_>, PdfThumbError> {
+    if password.is_empty() {
+        return PdfDocument_::LoadFromStreamAsync(stream).map_err(Into::into);
+    }
+    let password = HSTRING::from(password);
+    PdfDocument_::LoadFromStreamWithPasswordAsync(stream, &password).map_err(Into::into)
+}
+
+/// Get the file of the path. A relative path is resolved against the current directory, since WinRT requires an absolute path.
+fn get_file<P: AsRef<Path>>(path: P) -> Result<IAsyncOperation<StorageFile>, PdfThumbError> {
+    let path = path.as_ref();
+    let absolute =
+        std::path::absolute(path).map_err(|_| PdfThumbError::FileNotFound(path.to_path_buf()))?;
+    StorageFile::GetFileFromPathAsync(&HSTRING::from(absolute.as_path()))
+        .map_err(|e| file_error(e, path))
+}
+
+fn open(file: &StorageFile) -> Result<IAsyncOperation<PdfDocument_>, PdfThumbError> {
+    PdfDocument_::LoadFromFileAsync(file).map_err(Into::into)
+}
+
+fn open_with_password(
+    file: &StorageFile,
+    password: &str,
+) -> Result<IAsyncOperation<PdfDocument_>, PdfThumbError> {
+    if password.is_empty() {
+        return open(file);
+    }
+    let password = HSTRING::from(password);
+    PdfDocument_::LoadFromFileWithPasswordAsync(file, &password).map_err(Into::into)
+}
+
+fn render(
+    page: &PdfPage,
+    output: &InMemoryRandomAccessStream,
+    options: &PdfPageRenderOptions,
+) -> Result<IAsyncAction, PdfThumbError> {
+    page.RenderWithOptionsToStreamAsync(output, options)
+        .map_err(Into::into)
+}
+
+/// The render options converted from the last resolved [`Options`], reused while the resolved options stay the same.
+#[derive(Debug, Default)]
+struct RenderOptionsCache(Option<(Options, PdfPageRenderOptions)>);
+
+impl RenderOptionsCache {
+    fn get(&mut self, options: Options) -> Result<PdfPageRenderOptions, PdfThumbError> {
+        if let Some((cached, op)) = &self.0 {
+            if *cached == options {
+                return Ok(op.clone());
+            }
+        }
+        let op = PdfPageRenderOptions::try_from(options)?;
+        self.0 = Some((options, op.clone()));
+        Ok(op)
+    }
+}
+
+/// Wait for the action, cancelling it when `token` is cancelled.
+fn wait(action: IAsyncAction, token: Option<&CancellationToken>) -> Result<(), PdfThumbError> {
+    let _registration = cancel::register(token, &action)?;
+    action.get().map_err(codec_error)
+}
+
+/// Await the operation, cancelling it when `token` is cancelled. Returns `None` if it is cancelled.
+async fn until_cancelled<T: RuntimeType + 'static>(
+    operation: IAsyncOperation<T>,
+    token: &CancellationToken,
+) -> Option<windows::core::Result<T>> {
+    let result = match token.register(&operation) {
+        Ok(_registration) => operation.await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Err(_) if token.is_cancelled() => None,
+        result => Some(result),
+    }
+}
+
+async fn cancellable(
+    action: IAsyncAction,
+    token: Option<&CancellationToken>,
+) -> Result<(), PdfThumbError> {
+    let Some(token) = token else {
+        return action.await.map_err(codec_error);
+    };
+    let result = token.run(action).await;
+    if token.is_cancelled() {
+        return Err(PdfThumbError::Cancelled);
+    }
+    result.map_err(codec_error)
+}
+
+fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), PdfThumbError> {
+    let tmp = temp_path(path);
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, path).inspect_err(|_| {
+        std::fs::remove_file(&tmp).ok();
+    })?;
+    Ok(())
+}
+
+#[cfg(feature = "tokio")]
+async fn write_atomic_async(path: &Path, bytes: &[u8]) -> Result<(), PdfThumbError> {
+    let tmp = temp_path(path);
+    tokio::fs::write(&tmp, bytes).await?;
+    if let Err(e) = tokio::fs::rename(&tmp, path).await {
+        tokio::fs::remove_file(&tmp).await.ok();
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Assert at compile time that the future writing a file with `tokio::fs` is `Send`, so it can be spawned.
+#[cfg(feature = "tokio")]
+#[allow(dead_code)]
+const _: () = {
+    fn assert_send<T: Send>(_: T) {}
+    fn assert_future_send(doc: &PdfDocument, options: Options) {
+        assert_send(doc.thumb_to_file_async("", options));
+    }
+};
+
+/// A temporary file next to `path`, unique to this process and call so concurrent writers to the same path do not share it.
+fn temp_path(path: &Path) -> OsString {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    tmp
+}
+
+pub(crate) fn read_bytes(output: InMemoryRandomAccessStream) -> Result<Vec<u8>, PdfThumbError> {
+    let mut buf = Vec::with_capacity(output.Size()? as usize);
+    read_to(output, &mut buf)?;
+    Ok(buf)
+}
+
+/// The size of a chunk to read from a stream at once.
+const CHUNK_SIZE: u32 = 64 * 1024;
+
+/// The length of the next chunk to load when `remaining` bytes are left, which is never more than [`CHUNK_SIZE`].
+fn chunk_len(remaining: u64) -> u32 {
+    remaining.min(CHUNK_SIZE as u64) as u32
+}
+
+fn read_to<W: Write>(
+    output: InMemoryRandomAccessStream,
+    writer: &mut W,
+) -> Result<u64, PdfThumbError> {
+    let input = output.GetInputStreamAt(0)?;
+    let reader = DataReader::CreateDataReader(&input)?;
+    let size = output.Size()?;
+    let mut remaining = size;
+    let mut buf = vec![0; CHUNK_SIZE as usize];
+    while remaining > 0 {
+        let len = reader.LoadAsync(chunk_len(remaining))?.get()?;
+        if len == 0 {
+            break;
+        }
+        let chunk = &mut buf[..len as usize];
+        reader.ReadBytes(chunk)?;
+        writer.write_all(chunk)?;
+        remaining -= len as u64;
+    }
+    Ok(size - remaining)
+}
+
+/// Read `size` bytes from `reader` into `buf`, replacing its contents but reusing its allocation.
+fn read_into(reader: &DataReader, size: u64, buf: &mut Vec<u8>) -> Result<(), PdfThumbError> {
+    buf.resize(size as usize, 0);
+    let mut read = 0;
+    while read < buf.len() {
+        let len = reader
+            .LoadAsync(chunk_len((buf.len() - read) as u64))?
+            .get()? as usize;
+        if len == 0 {
+            break;
+        }
+        reader.ReadBytes(&mut buf[read..read + len])?;
+        read += len;
+    }
+    buf.truncate(read);
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct PdfPage {
+    page: PdfPage_,
+}
+
+unsafe impl Sync for PdfPage {}
+unsafe impl Send for PdfPage {}
+
+impl Deref for PdfPage {
+    type Target = PdfPage_;
+
+    fn deref(&self) -> &Self::Target {
+        &self.page
+    }
+}
+
+impl PdfPage {
+    pub fn new(page: PdfPage_) -> Self {
+        Self { page }
+    }
+
+    /// Generate a thumbnail image of the page with the specified options. `page` of the options is ignored.
+    pub fn thumb(&self, options: Options) -> Result<Vec<u8>, PdfThumbError> {
+        let output = self.render_to_stream(options, None)?;
+        read_bytes(output)
+    }
+
+    /// Generate a thumbnail image of the page with the specified options asynchronously. `page` of the options is ignored.
+    pub async fn thumb_async(&self, options: Options) -> Result<Vec<u8>, PdfThumbError> {
+        let output = self.render_to_stream_async(options, None).await?;
+        read_bytes(output)
+    }
+
+    fn render_to_stream(
+        &self,
+        options: Options,
+        token: Option<&CancellationToken>,
+    ) -> Result<InMemoryRandomAccessStream, PdfThumbError> {
+        let output = InMemoryRandomAccessStream::new()?;
+        self.render_into(&output, options, token, &mut RenderOptionsCache::default())?;
+        Ok(output)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(
+                page = self.index().ok(),
+                width = options.width,
+                height = options.height,
+                format = %options.image_format(),
+            ),
+        )
+    )]
+    fn render_into(
+        &self,
+        output: &InMemoryRandomAccessStream,
+        options: Options,
+        token: Option<&CancellationToken>,
+        cache: &mut RenderOptionsCache,
+    ) -> Result<(), PdfThumbError> {
+        #[cfg(feature = "tracing")]
+        let _elapsed = trace::Elapsed::start();
+        let options = options.resolve(self)?;
+        if encoder::is_required(&options) {
+            let input = InMemoryRandomAccessStream::new()?;
+            let action = render(self, &input, &cache.get(options.lossless())?)?;
+            wait(action, token)?;
+            encoder::encode(&input, output, &options, token)
+        } else {
+            let action = render(self, output, &cache.get(options)?)?;
+            wait(action, token)
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(
+                page = self.index().ok(),
+                width = options.width,
+                height = options.height,
+                format = %options.image_format(),
+            ),
+        )
+    )]
+    async fn render_to_stream_async(
+        &self,
+        options: Options,
+        token: Option<&CancellationToken>,
+    ) -> Result<InMemoryRandomAccessStream, PdfThumbError> {
+        #[cfg(feature = "tracing")]
+        let _elapsed = trace::Elapsed::start();
+        let options = options.resolve(self)?;
+        let output = InMemoryRandomAccessStream::new()?;
+        if encoder::is_required(&options) {
+            let input = InMemoryRandomAccessStream::new()?;
+            let action = render(self, &input, &options.lossless().try_into()?)?;
+            cancellable(action, token).await?;
+            encoder::encode_async(&input, &output, &options).await?;
+        } else {
+            let action = render(self, &output, &options.try_into()?)?;
+            cancellable(action, token).await?;
+        }
+        Ok(output)
+    }
+
+    /// Prepare the page for rendering. Calling it before rendering the same page repeatedly, like at multiple sizes, can noticeably reduce the latency of each render.
+    pub fn prepare(&self) -> Result<(), PdfThumbError> {
+        Ok(self.page.PreparePageAsync()?.get()?)
+    }
+
+    /// Prepare the page for rendering asynchronously. See [`PdfPage::prepare`].
+    pub async fn prepare_async(&self) -> Result<(), PdfThumbError> {
+        Ok(self.page.PreparePageAsync()?.await?)
+    }
+
+    /// Get the index of the page in the PDF document.
+    pub fn index(&self) -> Result<u32, PdfThumbError> {
+        Ok(self.page.Index()?)
+    }
+
+    /// Get the size of the page.
+    pub fn size(&self) -> Result<Size, PdfThumbError> {
+        Ok(self.page.Size()?.into())
+    }
+
+    /// Get the zoom level of the page preferred by the author of the PDF document.
+    pub fn preferred_zoom(&self) -> Result<f32, PdfThumbError> {
+        Ok(self.page.PreferredZoom()?)
+    }
+
+    /// Get the boxes of the page specified in the PDF document.
+    pub fn dimensions(&self) -> Result<PageDimensions, PdfThumbError> {
+        self.page.Dimensions()?.try_into()
+    }
+
+    /// Get the media box of the page.
+    pub fn media_box(&self) -> Result<Bounds, PdfThumbError> {
+        Ok(self.page.Dimensions()?.MediaBox()?.into())
+    }
+
+    /// Get the crop box of the page.
+    pub fn crop_box(&self) -> Result<Bounds, PdfThumbError> {
+        Ok(self.page.Dimensions()?.CropBox()?.into())
+    }
+
+    /// Get the bleed box of the page.
+    pub fn bleed_box(&self) -> Result<Bounds, PdfThumbError> {
+        Ok(self.page.Dimensions()?.BleedBox()?.into())
+    }
+
+    /// Get the trim box of the page.
+    pub fn trim_box(&self) -> Result<Bounds, PdfThumbError> {
+        Ok(self.page.Dimensions()?.TrimBox()?.into())
+    }
+
+    /// Get the art box of the page.
+    pub fn art_box(&self) -> Result<Bounds, PdfThumbError> {
+        Ok(self.page.Dimensions()?.ArtBox()?.into())
+    }
+
+    /// Get the rotation of the page specified in the PDF document.
+    pub fn rotation(&self) -> Result<PageRotation, PdfThumbError> {
+        Ok(self.page.Rotation()?.into())
+    }
+}
+
+impl Drop for PdfPage {
+    fn drop(&mut self) {
+        self.page.Close().ok();
+    }
+}
+
+/// A renderer which reuses its stream, reader and buffer between renders, so rendering many pages in a loop does not allocate once the buffer has grown to the largest image.
+///
+/// The WinRT render options converted from [`Options`] are also reused while the options resolved for each page stay the same.
+#[derive(Debug)]
+pub struct Renderer {
+    options: Options,
+    cache: RenderOptionsCache,
+    stream: InMemoryRandomAccessStream,
+    reader: DataReader,
+    buf: Vec<u8>,
+}
+
+unsafe impl Send for Renderer {}
+
+impl Renderer {
+    /// Create a renderer which renders pages with the default options by [`Renderer::render_page`].
+    pub fn new() -> Result<Self, PdfThumbError> {
+        Self::with_options(Options::default())
+    }
+
+    /// Create a renderer which renders pages with the specified options by [`Renderer::render_page`].
+    pub fn with_options(options: Options) -> Result<Self, PdfThumbError> {
+        let stream = InMemoryRandomAccessStream::new()?;
+        Ok(Self {
+            options,
+            cache: RenderOptionsCache::default(),
+            reader: DataReader::CreateDataReader(&stream)?,
+            stream,
+            buf: Vec::new(),
+        })
+    }
+
+    /// Generate a thumbnail image of the page with the specified options. `page` of the options is ignored.
+    ///
+    /// The returned image borrows the internal buffer, which is overwritten by the next render.
+    pub fn render(&mut self, page: &PdfPage, options: Options) -> Result<&[u8], PdfThumbError> {
+        self.stream.Seek(0)?;
+        self.stream.SetSize(0)?;
+        page.render_into(&self.stream, options, None, &mut self.cache)?;
+        self.stream.Seek(0)?;
+        read_into(&self.reader, self.stream.Size()?, &mut self.buf)?;
+        Ok(&self.buf)
+    }
+
+    /// Generate a thumbnail image of the page with the options of the renderer. `page` of the options is ignored.
+    ///
+    /// The returned image borrows the internal buffer, which is overwritten by the next render.
+    pub fn render_page(&mut self, page: &PdfPage) -> Result<&[u8], PdfThumbError> {
+        self.render(page, self.options)
+    }
+}
+
+impl From<Foundation::Rect> for Bounds {
+    fn from(r: Foundation::Rect) -> Self {
+        Self {
+            x: r.X,
+            y: r.Y,
+            width: r.Width,
+            height: r.Height,
+        }
+    }
+}
+
+impl TryFrom<PdfPageDimensions> for PageDimensions {
+    type Error = PdfThumbError;
+    fn try_from(d: PdfPageDimensions) -> Result<Self, Self::Error> {
+        Ok(Self {
+            media_box: d.MediaBox()?.into(),
+            crop_box: d.CropBox()?.into(),
+            bleed_box: d.BleedBox()?.into(),
+            trim_box: d.TrimBox()?.into(),
+            art_box: d.ArtBox()?.into(),
+        })
+    }
+}
+
+impl From<PdfPageRotation> for PageRotation {
+    fn from(value: PdfPageRotation) -> Self {
+        match value {
+            PdfPageRotation::Rotate90 => Self::Rotate90,
+            PdfPageRotation::Rotate180 => Self::Rotate180,
+            PdfPageRotation::Rotate270 => Self::Rotate270,
+            _ => Self::Normal,
+        }
+    }
+}
+
+impl From<Foundation::Size> for Size {
+    fn from(value: Foundation::Size) -> Self {
+        Self {
+            width: value.Width,
+            height: value.Height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A PDF document of `pages` pages of US Letter size, each with a different pattern of colored stripes.
+    fn sample(pages: u32) -> PdfDocument {
+        PdfDocument::load(&sample_bytes(pages)).unwrap()
+    }
+
+    /// The bytes of [`sample`].
+    fn sample_bytes(pages: u32) -> Vec<u8> {
+        let kids = (0..pages)
+            .map(|i| format!("{} 0 R", 3 + i * 2))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_owned(),
+            format!("<< /Type /Pages /Kids [{kids}] /Count {pages} >>"),
+        ];
+        for i in 0..pages {
+            let content = (0..40)
+                .map(|j| {
+                    let (r, g, b) = ((j * 7 + i) % 10, (j * 3) % 10, (j + i * 5) % 10);
+                    format!(
+                        "0.{r} 0.{g} 0.{b} rg {} {} 300 15 re f",
+                        36 + j * 5,
+                        36 + j * 18
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents {} 0 R >>",
+                4 + i * 2
+            ));
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            ));
+        }
+        build(&objects, "")
+    }
+
+    /// A one-page PDF document encrypted by the standard security handler with 40-bit RC4, whose user password is `secret`.
+    ///
+    /// The page has no content, so no string or stream has to be encrypted. `/O` and `/U` are computed for the owner password `owner`, `/P -4` and the first `/ID`.
+    fn encrypted() -> Vec<u8> {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] >>",
+            "<< /Filter /Standard /V 1 /R 2 /P -4 \
+             /O <92fe0f4454ad4c9644693f33c07cb54f587dce1e2682fe9ecea6107a1ef630dd> \
+             /U <49ecc2152a5aa9a4640923d0daf38ac2ceab69380b5caf562e5b9c72ee266a25> >>",
+        ]
+        .map(str::to_owned);
+        build(
+            &objects,
+            "/Encrypt 4 0 R /ID [<0123456789abcdef0123456789abcdef> <0123456789abcdef0123456789abcdef>]",
+        )
+    }
+
+    /// Serialize `objects`, numbered from 1 with the first as the catalog, into a PDF file with `trailer` added to the trailer dictionary.
+    fn build(objects: &[String], trailer: &str) -> Vec<u8> {
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n{object}\nendobj\n", i + 1).bytes());
+        }
+        let xref = pdf.len();
+        pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+        for offset in offsets {
+            pdf.extend(format!("{offset:010} 00000 n \n").bytes());
+        }
+        pdf.extend(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R {trailer} >>\nstartxref\n{xref}\n%%EOF\n",
+                objects.len() + 1
+            )
+            .bytes(),
+        );
+        pdf
+    }
+
+    /// Write `bytes` to a file in the temporary directory, named uniquely to this process and `name`.
+    fn temp_file(name: &str, bytes: &[u8]) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("pdf-thumb-{}-{name}.pdf", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn password_on_unencrypted() {
+        let path = temp_file("unencrypted", &sample_bytes(1));
+        let doc = PdfDocument::open_with_password(&path, "secret").unwrap();
+        assert!(!doc.is_password_protected().unwrap());
+        assert_eq!(doc.thumb().unwrap(), sample(1).thumb().unwrap());
+        drop(doc);
+        std::fs::remove_file(path).ok();
+
+        let doc = PdfDocument::load_with_password(&sample_bytes(1), "secret").unwrap();
+        assert!(doc.thumb().is_ok());
+    }
+
+    #[test]
+    fn open_relative_path() {
+        let name = format!("pdf-thumb-{}-relative.pdf", std::process::id());
+        let path = Path::new(&name);
+        assert!(path.is_relative());
+        std::fs::write(path, sample_bytes(2)).unwrap();
+        let result = PdfDocument::open(path).and_then(|doc| doc.page_count());
+        std::fs::remove_file(path).ok();
+        assert_eq!(result.unwrap(), 2);
+    }
+
+    #[test]
+    fn clones_render_concurrently() {
+        let pdf = sample(4);
+        let options = Options::builder().width(200).build();
+        let sequential = pdf.thumb_all(options).unwrap();
+        let (a, b) = (pdf.clone(), pdf.clone());
+        let render = |doc: &PdfDocument, pages: [u32; 2]| {
+            pages.map(|page| doc.thumb_with_options(Options { page, ..options }).unwrap())
+        };
+        let (even, odd) = std::thread::scope(|s| {
+            let even = s.spawn(|| render(&a, [0, 2]));
+            let odd = s.spawn(|| render(&b, [1, 3]));
+            (even.join().unwrap(), odd.join().unwrap())
+        });
+        assert_eq!(even, [sequential[0].clone(), sequential[2].clone()]);
+        assert_eq!(odd, [sequential[1].clone(), sequential[3].clone()]);
+    }
+
+    #[test]
+    fn file_released_on_drop() {
+        let path = temp_file("released", &sample_bytes(1));
+        let doc = PdfDocument::open(&path).unwrap();
+        let clone = doc.clone();
+        let page = doc.get_page(0).unwrap();
+        assert!(clone.thumb().is_ok());
+        drop((doc, clone, page));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn renderer() {
+        let pdf = sample(3);
+        let mut renderer = Renderer::with_options(Options::builder().width(200).build()).unwrap();
+        for page in 0..3 {
+            let expected = pdf
+                .thumb_with_options(Options::builder().width(200).page(page).build())
+                .unwrap();
+            let page = pdf.get_page(page).unwrap();
+            assert_eq!(renderer.render_page(&page).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn password_required() {
+        let pdf = encrypted();
+        assert!(matches!(
+            PdfDocument::load(&pdf),
+            Err(PdfThumbError::PasswordRequired)
+        ));
+        assert!(matches!(
+            PdfDocument::load_with_password(&pdf, "wrong"),
+            Err(PdfThumbError::WrongPassword)
+        ));
+        let doc = PdfDocument::load_with_password(&pdf, "secret").unwrap();
+        assert!(doc.is_password_protected().unwrap());
+        assert!(doc.thumb().is_ok());
+    }
+
+    #[test]
+    fn timeout() {
+        let pdf = sample(1);
+        let options = Options::builder().width(8000).build();
+        let result = pdf.thumb_with_options_timeout(options, Duration::from_nanos(1));
+        assert!(matches!(result, Err(PdfThumbError::Timeout)), "{result:?}");
+        assert!(pdf
+            .thumb_with_options_timeout(options, Duration::from_secs(60))
+            .is_ok());
+    }
+
+    #[test]
+    fn thumb_all_parallel() {
+        let pdf = sample(5);
+        let options = Options::builder().width(200).build();
+        let sequential = pdf.thumb_all(options).unwrap();
+        assert_eq!(sequential.len(), 5);
+        for threads in [1, 3, 8] {
+            assert_eq!(
+                pdf.thumb_all_parallel(options, threads).unwrap(),
+                sequential
+            );
+        }
+    }
+
+    #[test]
+    fn thumb_all_parallel_more_threads_than_pages() {
+        let pdf = sample(2);
+        let options = Options::builder().width(100).build();
+        assert_eq!(
+            pdf.thumb_all_parallel(options, 64).unwrap(),
+            pdf.thumb_all(options).unwrap()
+        );
+    }
+
+    #[test]
+    fn heif_round_trip() {
+        let pdf = sample(1);
+        let options = Options::builder()
+            .format(ImageFormat::Heif)
+            .width(200)
+            .build();
+        let (heif, width, height) = match pdf.thumb_with_dimensions(options) {
+            Err(PdfThumbError::CodecNotInstalled | PdfThumbError::UnsupportedFormat) => return,
+            result => result.unwrap(),
+        };
+        assert_eq!(&heif[4..8], b"ftyp");
+        let (_, png_width, png_height) = pdf
+            .thumb_with_dimensions(Options {
+                format: Some(ImageFormat::Png),
+                ..options
+            })
+            .unwrap();
+        assert_eq!((width, height), (png_width, png_height));
+    }
+
+    #[test]
+    fn preserve_source_aspect() {
+        let pdf = sample(1);
+        let size = pdf.get_page(0).unwrap().size().unwrap();
+        let options = Options::builder()
+            .width(300)
+            .height(100)
+            .preserve_source_aspect(true)
+            .build();
+        let (_, width, height) = pdf.thumb_with_dimensions(options).unwrap();
+        let expected = (300.0 * size.height() / size.width()).round() as u32;
+        assert_eq!((width, height), (300, expected));
+
+        let options = Options {
+            rect: Rect::new(0, 0, 200, 50),
+            ..options
+        };
+        let (_, width, height) = pdf.thumb_with_dimensions(options).unwrap();
+        assert_eq!((width, height), (300, 75));
+
+        let options = Options {
+            rect: Rect::new(0, 0, 0, 50),
+            ..options
+        };
+        assert!(matches!(
+            pdf.thumb_with_dimensions(options),
+            Err(PdfThumbError::InvalidOptions(_))
+        ));
+    }
+
+    #[test]
+    fn rendered_size_matches_render() {
+        let pdf = sample(1);
+        let page_size = pdf.get_page(0).unwrap().size().unwrap();
+        let base = Options::builder().rect(Rect::new(10, 20, 300, 200));
+        for options in [
+            Options::builder().width(300).build(),
+            Options::builder().height(300).build(),
+            Options::builder().dpi(48.0).build(),
+            base.dpi(48.0).build(),
+            base.width(150).build(),
+            Options::builder()
+                .width(300)
+                .height(300)
+                .scale_mode(ScaleMode::Fit)
+                .build(),
+            Options::builder()
+                .width(300)
+                .height(100)
+                .scale_mode(ScaleMode::Fill)
+                .build(),
+            Options::builder()
+                .width(300)
+                .rotate(crate::Rotation::Cw90)
+                .build(),
+        ] {
+            let (_, width, height) = pdf.thumb_with_dimensions(options).unwrap();
+            let size = options.rendered_size(page_size);
+            assert_eq!(
+                (width as f32, height as f32),
+                (size.width(), size.height()),
+                "{options:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_custom_format() {
+        let pdf = sample(1);
+        let options = Options::builder()
+            .format(ImageFormat::Custom(GUID::new().unwrap()))
+            .build();
+        assert!(matches!(
+            pdf.thumb_with_options(options),
+            Err(PdfThumbError::UnsupportedFormat)
+        ));
+    }
+
+    #[test]
+    fn jpeg_quality() {
+        let pdf = sample(1);
+        let options = Options::builder().format(ImageFormat::Jpeg).width(600);
+        let low = pdf
+            .thumb_with_options(options.jpeg_quality(0.1).build())
+            .unwrap();
+        let high = pdf
+            .thumb_with_options(options.jpeg_quality(0.95).build())
+            .unwrap();
+        assert!(low.len() < high.len(), "{} >= {}", low.len(), high.len());
+    }
+
+    #[test]
+    fn chunk_len_boundaries() {
+        let chunk = CHUNK_SIZE as u64;
+        assert_eq!(chunk_len(0), 0);
+        assert_eq!(chunk_len(1), 1);
+        assert_eq!(chunk_len(chunk - 1), CHUNK_SIZE - 1);
+        assert_eq!(chunk_len(chunk), CHUNK_SIZE);
+        assert_eq!(chunk_len(chunk * 3), CHUNK_SIZE);
+        assert_eq!(chunk_len(u32::MAX as u64), CHUNK_SIZE);
+        assert_eq!(chunk_len(u32::MAX as u64 + 1), CHUNK_SIZE);
+        assert_eq!(chunk_len(u64::MAX), CHUNK_SIZE);
+
+        for size in [chunk * 3, u32::MAX as u64, u32::MAX as u64 + 1] {
+            let (mut remaining, mut chunks) = (size, 0);
+            while remaining > 0 {
+                remaining -= chunk_len(remaining) as u64;
+                chunks += 1;
+            }
+            assert_eq!(chunks, size.div_ceil(chunk));
+        }
+    }
+}